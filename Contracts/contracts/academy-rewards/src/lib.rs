@@ -1,6 +1,6 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, contracterror, Address, Env, String, Symbol};
+use soroban_sdk::{contract, contractimpl, contracttype, contracterror, Address, BytesN, Env, String, Symbol};
 
 // Contract Errors
 #[contracterror]
@@ -226,6 +226,15 @@ impl AcademyRewardsContract {
         Ok(())
     }
 
+    /// Install new contract code (admin only, e.g. the governance contract
+    /// executing an approved upgrade proposal)
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) -> Result<(), ContractError> {
+        Self::require_admin(&env, &admin)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(())
+    }
+
     // ========== USER FUNCTIONS ==========
 
     /// Redeem badge for fee discount
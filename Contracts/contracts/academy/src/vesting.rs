@@ -1,5 +1,4 @@
-#![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, Symbol, symbol_short, Vec};
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, Address, BytesN, Env, symbol_short};
 
 /// Vesting schedule for an academy reward
 #[contracttype]
@@ -50,6 +49,7 @@ pub struct RevokeEvent {
 }
 
 /// Vesting error codes
+#[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum VestingError {
@@ -164,7 +164,7 @@ impl AcademyVestingContract {
             .storage()
             .persistent()
             .get(&schedules_key)
-            .unwrap_or_else(|_| soroban_sdk::Map::new(&env));
+            .unwrap_or_else(|| soroban_sdk::Map::new(&env));
 
         schedules.set(next_id, schedule);
         env.storage().persistent().set(&schedules_key, &schedules);
@@ -412,6 +412,30 @@ impl AcademyVestingContract {
         Ok(vested_amount as i128)
     }
 
+    /// Install new contract code (governance only)
+    pub fn upgrade(
+        env: Env,
+        governance: Address,
+        new_wasm_hash: BytesN<32>,
+    ) -> Result<(), VestingError> {
+        governance.require_auth();
+
+        let gov_key = symbol_short!("gov");
+        let stored_governance: Address = env
+            .storage()
+            .persistent()
+            .get(&gov_key)
+            .ok_or(VestingError::Unauthorized)?;
+
+        if governance != stored_governance {
+            return Err(VestingError::Unauthorized);
+        }
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+
+        Ok(())
+    }
+
     /// Get contract information
     pub fn get_info(env: Env) -> Result<(Address, Address, Address), VestingError> {
         let admin_key = symbol_short!("admin");
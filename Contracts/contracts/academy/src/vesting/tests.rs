@@ -0,0 +1,61 @@
+use super::*;
+use soroban_sdk::{testutils::Address as _, Symbol};
+
+const WASM_V1: &[u8] = include_bytes!("../../testdata/version_v1.wasm");
+
+fn setup<'a>(env: &Env) -> (AcademyVestingContractClient<'a>, Address, Address) {
+    let contract_id = env.register_contract(None, AcademyVestingContract);
+    let client = AcademyVestingContractClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    let governance = Address::generate(env);
+    client.init(&admin, &Address::generate(env), &governance);
+
+    (client, admin, governance)
+}
+
+#[test]
+fn test_grant_requires_stored_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, governance) = setup(&env);
+    let beneficiary = Address::generate(&env);
+
+    let id = client.grant_vesting(&admin, &beneficiary, &1_000, &0, &100, &1_000);
+    assert_eq!(env.auths()[0].0, admin);
+    assert_eq!(client.get_vesting(&id).beneficiary, beneficiary);
+
+    // Governance upgrades the code but doesn't administer grants
+    let result = client.try_grant_vesting(&governance, &beneficiary, &1_000, &0, &100, &1_000);
+    assert_eq!(result, Err(Ok(VestingError::Unauthorized)));
+}
+
+#[test]
+fn test_governance_upgrades_contract() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, governance) = setup(&env);
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+
+    client.upgrade(&governance, &v1);
+    assert_eq!(env.auths()[0].0, governance);
+
+    let version = Symbol::new(&env, "version");
+    let installed: u32 = env.invoke_contract(&client.address, &version, soroban_sdk::vec![&env]);
+    assert_eq!(installed, 1);
+}
+
+#[test]
+fn test_only_governance_can_upgrade() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, _) = setup(&env);
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+
+    assert_eq!(client.try_upgrade(&admin, &v1), Err(Ok(VestingError::Unauthorized)));
+    let stranger = Address::generate(&env);
+    assert_eq!(client.try_upgrade(&stranger, &v1), Err(Ok(VestingError::Unauthorized)));
+}
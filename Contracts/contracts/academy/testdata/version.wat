@@ -0,0 +1,16 @@
+;; Minimal Soroban contract used as an upgrade target in tests. It follows
+;; the `upgrade(admin, new_wasm_hash)` convention the governance contract
+;; calls on its targets; version_v1.wasm is this module as written.
+(module
+  ;; update_current_contract_wasm(hash: BytesObject) -> Void
+  (import "l" "6" (func $update_current_contract_wasm (param i64) (result i64)))
+
+  (func (export "version") (result i64)
+    ;; U32Val(1): value in the upper 32 bits, tag 4 in the low byte
+    (i64.const 0x0000000100000004))
+
+  (func (export "upgrade") (param $admin i64) (param $hash i64) (result i64)
+    (call $update_current_contract_wasm (local.get $hash)))
+
+  ;; ScEnvMetaEntry::InterfaceVersion for protocol 20
+  (@custom "contractenvmetav0" "\00\00\00\00\00\00\00\14\00\00\00\00"))
@@ -0,0 +1,381 @@
+#![no_std]
+//! Standalone governance contract.
+//!
+//! Owns the roles, proposals and timelock that other contracts otherwise
+//! embed through `shared::governance`. Contracts that name this contract as
+//! their admin are changed only through its proposals, which gives every
+//! Stellara contract one control plane and one audit trail of events.
+
+use soroban_sdk::{contract, contractimpl, symbol_short, Address, BytesN, Env, Symbol, Val, Vec};
+use shared::governance::{
+    GovernanceConfig, GovernanceError, GovernanceManager, GovernanceRole, Proposal,
+    ProposalAction, ProposalStatus, RoleChange, UpgradeProposal,
+};
+
+#[contract]
+pub struct GovernanceContract;
+
+#[contractimpl]
+impl GovernanceContract {
+    /// Initialize with the first admin, approvers and executor. The admin
+    /// must sign; deploy and initialize in the same transaction so nobody
+    /// else can claim the roles in between.
+    pub fn init(
+        env: Env,
+        admin: Address,
+        approvers: Vec<Address>,
+        executor: Address,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        let init_key = symbol_short!("init");
+        if env.storage().persistent().has(&init_key) {
+            return Err(GovernanceError::AlreadyInitialized);
+        }
+
+        env.storage().persistent().set(&init_key, &true);
+
+        GovernanceManager::assign_role(&env, admin, GovernanceRole::Admin);
+        for approver in approvers.iter() {
+            GovernanceManager::assign_role(&env, approver, GovernanceRole::Approver);
+        }
+        GovernanceManager::assign_role(&env, executor, GovernanceRole::Executor);
+
+        Ok(())
+    }
+
+    // ========== ROLES ==========
+
+    /// Schedule granting a role; it takes effect after the role-change timelock (admin only)
+    pub fn grant_role(
+        env: Env,
+        admin: Address,
+        role: GovernanceRole,
+        account: Address,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::grant_role(&env, admin, role, account)
+    }
+
+    /// Schedule revoking a role; it takes effect after the role-change timelock (admin only)
+    pub fn revoke_role(
+        env: Env,
+        admin: Address,
+        role: GovernanceRole,
+        account: Address,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::revoke_role(&env, admin, role, account)
+    }
+
+    /// Apply a scheduled role change once its timelock has passed
+    pub fn execute_role_change(
+        env: Env,
+        change_id: u64,
+        executor: Address,
+    ) -> Result<(), GovernanceError> {
+        executor.require_auth();
+
+        GovernanceManager::execute_role_change(&env, change_id, executor)
+    }
+
+    /// Cancel a scheduled role change (admin only)
+    pub fn cancel_role_change(
+        env: Env,
+        change_id: u64,
+        admin: Address,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::cancel_role_change(&env, change_id, admin)
+    }
+
+    /// Give up one of the caller's own roles immediately
+    pub fn renounce_role(
+        env: Env,
+        account: Address,
+        role: GovernanceRole,
+    ) -> Result<(), GovernanceError> {
+        account.require_auth();
+
+        GovernanceManager::renounce_role(&env, account, role)
+    }
+
+    /// Configure which roles holders of `role` also act as (admin only)
+    pub fn set_role_hierarchy(
+        env: Env,
+        admin: Address,
+        role: GovernanceRole,
+        implied: Vec<GovernanceRole>,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::set_implied_roles(&env, admin, role, implied)
+    }
+
+    pub fn get_role_hierarchy(env: Env, role: GovernanceRole) -> Vec<GovernanceRole> {
+        GovernanceManager::get_implied_roles(&env, role)
+    }
+
+    pub fn get_role_change(env: Env, change_id: u64) -> Result<RoleChange, GovernanceError> {
+        GovernanceManager::get_role_change(&env, change_id)
+    }
+
+    pub fn get_role_members(env: Env, role: GovernanceRole) -> Vec<Address> {
+        GovernanceManager::get_role_members(&env, role)
+    }
+
+    pub fn has_role(env: Env, account: Address, role: GovernanceRole) -> bool {
+        GovernanceManager::has_role(&env, &account, role)
+    }
+
+    // ========== CONFIG ==========
+
+    /// Set proposal windows and timelock bounds (admin only)
+    pub fn set_config(
+        env: Env,
+        admin: Address,
+        config: GovernanceConfig,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::set_config(&env, admin, config)
+    }
+
+    pub fn get_config(env: Env) -> GovernanceConfig {
+        GovernanceManager::get_config(&env)
+    }
+
+    // ========== UPGRADES ==========
+
+    /// Propose installing `new_contract_hash` on `target`, which must
+    /// accept this contract as its admin
+    #[allow(clippy::too_many_arguments)]
+    pub fn propose_upgrade(
+        env: Env,
+        admin: Address,
+        target: Address,
+        new_contract_hash: BytesN<32>,
+        description: Symbol,
+        approvers: Vec<Address>,
+        approval_threshold: u32,
+        timelock_delay: u64,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::propose_upgrade(
+            &env,
+            admin,
+            new_contract_hash,
+            target,
+            description,
+            approval_threshold,
+            approvers,
+            timelock_delay,
+        )
+    }
+
+    pub fn approve_upgrade(
+        env: Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        approver.require_auth();
+
+        GovernanceManager::approve_proposal(&env, proposal_id, approver)
+    }
+
+    pub fn execute_upgrade(
+        env: Env,
+        proposal_id: u64,
+        executor: Address,
+    ) -> Result<(), GovernanceError> {
+        executor.require_auth();
+
+        GovernanceManager::execute_proposal(&env, proposal_id, executor)
+    }
+
+    pub fn reject_upgrade(
+        env: Env,
+        proposal_id: u64,
+        rejector: Address,
+    ) -> Result<(), GovernanceError> {
+        rejector.require_auth();
+
+        GovernanceManager::reject_proposal(&env, proposal_id, rejector)
+    }
+
+    pub fn cancel_upgrade(
+        env: Env,
+        proposal_id: u64,
+        admin: Address,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::cancel_proposal(&env, proposal_id, admin)
+    }
+
+    pub fn get_upgrade_proposal(
+        env: Env,
+        proposal_id: u64,
+    ) -> Result<UpgradeProposal, GovernanceError> {
+        GovernanceManager::get_proposal(&env, proposal_id)
+    }
+
+    /// Hash this contract last installed on `target`
+    pub fn get_target_wasm_hash(env: Env, target: Address) -> Option<BytesN<32>> {
+        GovernanceManager::get_target_wasm_hash(&env, &target)
+    }
+
+    // ========== ACTIONS ==========
+
+    /// Propose a list of contract calls, e.g. parameter changes on contracts
+    /// that name this contract as their admin
+    pub fn propose_actions(
+        env: Env,
+        admin: Address,
+        actions: Vec<ProposalAction>,
+        description: Symbol,
+        approvers: Vec<Address>,
+        approval_threshold: u32,
+        timelock_delay: u64,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::propose_actions(
+            &env,
+            admin,
+            actions,
+            description,
+            approval_threshold,
+            approvers,
+            timelock_delay,
+        )
+    }
+
+    /// Propose emergency actions: shorter timelock, higher approval threshold
+    pub fn propose_emergency_actions(
+        env: Env,
+        admin: Address,
+        actions: Vec<ProposalAction>,
+        description: Symbol,
+        approvers: Vec<Address>,
+        approval_threshold: u32,
+        timelock_delay: u64,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::propose_emergency_actions(
+            &env,
+            admin,
+            actions,
+            description,
+            approval_threshold,
+            approvers,
+            timelock_delay,
+        )
+    }
+
+    pub fn approve_actions(
+        env: Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        approver.require_auth();
+
+        GovernanceManager::approve_actions(&env, proposal_id, approver)
+    }
+
+    /// Run an approved action proposal, returning each call's result
+    pub fn execute_actions(
+        env: Env,
+        proposal_id: u64,
+        executor: Address,
+    ) -> Result<Vec<Val>, GovernanceError> {
+        executor.require_auth();
+
+        GovernanceManager::execute_actions(&env, proposal_id, executor)
+    }
+
+    pub fn reject_actions(
+        env: Env,
+        proposal_id: u64,
+        rejector: Address,
+    ) -> Result<(), GovernanceError> {
+        rejector.require_auth();
+
+        GovernanceManager::reject_actions(&env, proposal_id, rejector)
+    }
+
+    pub fn cancel_actions(
+        env: Env,
+        proposal_id: u64,
+        admin: Address,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::cancel_actions(&env, proposal_id, admin)
+    }
+
+    pub fn get_action_proposal(env: Env, proposal_id: u64) -> Result<Proposal, GovernanceError> {
+        GovernanceManager::get_action_proposal(&env, proposal_id)
+    }
+
+    // ========== APPROVALS, VETO & EXPIRY ==========
+
+    pub fn revoke_approval(
+        env: Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        approver.require_auth();
+
+        GovernanceManager::revoke_approval(&env, proposal_id, approver)
+    }
+
+    /// Veto a proposal during its timelock (guardian only)
+    pub fn veto_proposal(
+        env: Env,
+        proposal_id: u64,
+        guardian: Address,
+    ) -> Result<(), GovernanceError> {
+        guardian.require_auth();
+
+        GovernanceManager::veto_proposal(&env, proposal_id, guardian)
+    }
+
+    pub fn get_expirable_proposals(env: Env, after_id: u64, limit: u32) -> Vec<u64> {
+        GovernanceManager::get_expirable_proposals(&env, after_id, limit)
+    }
+
+    pub fn get_proposal_count(env: Env) -> u64 {
+        GovernanceManager::proposal_count(&env)
+    }
+
+    pub fn expire_proposal(env: Env, proposal_id: u64) -> Result<(), GovernanceError> {
+        GovernanceManager::expire_proposal(&env, proposal_id)
+    }
+
+    pub fn list_proposals(
+        env: Env,
+        status: Option<ProposalStatus>,
+        after_id: u64,
+        limit: u32,
+    ) -> Vec<UpgradeProposal> {
+        GovernanceManager::list_proposals(&env, status, after_id, limit)
+    }
+
+    pub fn list_action_proposals(
+        env: Env,
+        status: Option<ProposalStatus>,
+        after_id: u64,
+        limit: u32,
+    ) -> Vec<Proposal> {
+        GovernanceManager::list_action_proposals(&env, status, after_id, limit)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,184 @@
+#![cfg(test)]
+
+use super::*;
+use academy_rewards::{AcademyRewardsContract, AcademyRewardsContractClient};
+use token::{TokenContract, TokenContractClient};
+use soroban_sdk::{testutils::Address as _, testutils::Events as _, testutils::Ledger as _, IntoVal, String};
+
+const WASM_V1: &[u8] = include_bytes!("../testdata/version_v1.wasm");
+
+fn setup<'a>(env: &Env) -> (GovernanceContractClient<'a>, Address, Address, Address) {
+    let contract_id = env.register_contract(None, GovernanceContract);
+    let client = GovernanceContractClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    let approver = Address::generate(env);
+    let executor = Address::generate(env);
+    client.init(&admin, &soroban_sdk::vec![env, approver.clone()], &executor);
+
+    (client, admin, approver, executor)
+}
+
+/// Academy rewards contract whose admin is the governance contract
+fn setup_rewards<'a>(env: &Env, governance: &Address) -> AcademyRewardsContractClient<'a> {
+    let rewards_id = env.register_contract(None, AcademyRewardsContract);
+    let rewards = AcademyRewardsContractClient::new(env, &rewards_id);
+    rewards.initialize(governance);
+    rewards
+}
+
+#[test]
+fn test_init_only_once() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approver, executor) = setup(&env);
+    assert_eq!(env.auths()[0].0, admin);
+    assert!(client.has_role(&admin, &GovernanceRole::Admin));
+    assert!(client.has_role(&approver, &GovernanceRole::Approver));
+    assert!(client.has_role(&executor, &GovernanceRole::Executor));
+
+    let result = client.try_init(&admin, &soroban_sdk::vec![&env, approver], &executor);
+    assert_eq!(result, Err(Ok(GovernanceError::AlreadyInitialized)));
+}
+
+#[test]
+fn test_action_proposal_administers_target_contract() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, approver, executor) = setup(&env);
+    let rewards = setup_rewards(&env, &client.address);
+
+    let create_badge = ProposalAction {
+        target: rewards.address.clone(),
+        function: Symbol::new(&env, "create_badge_type"),
+        args: soroban_sdk::vec![
+            &env,
+            client.address.into_val(&env),
+            1u32.into_val(&env),
+            String::from_str(&env, "Gold").into_val(&env),
+            1_000u32.into_val(&env),
+            5u32.into_val(&env),
+            0u64.into_val(&env),
+        ],
+    };
+    let actions = soroban_sdk::vec![&env, create_badge];
+    let approvers = soroban_sdk::vec![&env, approver.clone()];
+
+    let id = client.propose_actions(&admin, &actions, &symbol_short!("Badge"), &approvers, &1, &3_600);
+    client.approve_actions(&id, &approver);
+
+    // Nothing reaches the target before the timelock ends
+    assert_eq!(
+        client.try_execute_actions(&id, &executor),
+        Err(Ok(GovernanceError::TimelockNotExpired))
+    );
+    assert!(rewards.get_badge_metadata(&1).is_none());
+
+    env.ledger().with_mut(|li| li.timestamp = 4_600);
+    client.execute_actions(&id, &executor);
+
+    let metadata = rewards.get_badge_metadata(&1).unwrap();
+    assert_eq!(metadata.discount_bps, 1_000);
+    assert_eq!(metadata.max_redemptions, 5);
+    assert_eq!(client.get_action_proposal(&id).status, ProposalStatus::Executed);
+}
+
+#[test]
+fn test_upgrade_proposal_installs_wasm_on_target() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approver, executor) = setup(&env);
+    let rewards = setup_rewards(&env, &client.address);
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+    let approvers = soroban_sdk::vec![&env, approver.clone()];
+
+    let id = client.propose_upgrade(
+        &admin,
+        &rewards.address,
+        &v1,
+        &symbol_short!("Upgrade"),
+        &approvers,
+        &1,
+        &3_600,
+    );
+    client.approve_upgrade(&id, &approver);
+    env.ledger().with_mut(|li| li.timestamp = 3_600);
+    client.execute_upgrade(&id, &executor);
+
+    // The upgrade is recorded in the governance contract's event stream
+    let event = env.events().all().last().unwrap();
+    assert_eq!(event.0, client.address);
+    let (old, new): (Option<BytesN<32>>, BytesN<32>) = event.2.into_val(&env);
+    assert_eq!((old, new), (None, v1.clone()));
+
+    assert_eq!(client.get_target_wasm_hash(&rewards.address), Some(v1.clone()));
+    assert_eq!(client.get_upgrade_proposal(&id).status, ProposalStatus::Executed);
+
+    let version = Symbol::new(&env, "version");
+    let installed: u32 = env.invoke_contract(&rewards.address, &version, soroban_sdk::vec![&env]);
+    assert_eq!(installed, 1);
+}
+
+#[test]
+fn test_target_rejects_upgrade_from_other_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, _, _) = setup(&env);
+    let rewards = setup_rewards(&env, &client.address);
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+
+    let stranger = Address::generate(&env);
+    assert!(rewards.try_upgrade(&stranger, &v1).is_err());
+    assert_eq!(client.get_target_wasm_hash(&rewards.address), None);
+}
+
+#[test]
+fn test_governance_administers_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approver, executor) = setup(&env);
+    let token_id = env.register_contract(None, TokenContract);
+    let token = TokenContractClient::new(&env, &token_id);
+    token.initialize(&client.address);
+    let approvers = soroban_sdk::vec![&env, approver.clone()];
+
+    let recipient = Address::generate(&env);
+    let mint = ProposalAction {
+        target: token_id.clone(),
+        function: symbol_short!("mint"),
+        args: soroban_sdk::vec![&env, recipient.into_val(&env), 500i128.into_val(&env)],
+    };
+    let id = client.propose_actions(
+        &admin,
+        &soroban_sdk::vec![&env, mint],
+        &symbol_short!("Mint"),
+        &approvers,
+        &1,
+        &3_600,
+    );
+    client.approve_actions(&id, &approver);
+    env.ledger().with_mut(|li| li.timestamp = 3_600);
+    client.execute_actions(&id, &executor);
+    assert_eq!(token.balance(&recipient), 500);
+
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+    let id = client.propose_upgrade(
+        &admin,
+        &token_id,
+        &v1,
+        &symbol_short!("Upgrade"),
+        &approvers,
+        &1,
+        &3_600,
+    );
+    client.approve_upgrade(&id, &approver);
+    env.ledger().with_mut(|li| li.timestamp = 7_200);
+    client.execute_upgrade(&id, &executor);
+    assert_eq!(client.get_target_wasm_hash(&token_id), Some(v1));
+}
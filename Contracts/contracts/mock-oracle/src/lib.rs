@@ -0,0 +1,33 @@
+#![no_std]
+//! SEP-40 style price oracle for tests. Anyone can set prices.
+
+use soroban_sdk::{contract, contractimpl, contracttype, Env};
+use shared::oracle::{Asset, PriceData};
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Price(Asset),
+}
+
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    /// Set the price reported for an asset
+    pub fn set_price(env: Env, asset: Asset, price: i128, timestamp: u64) {
+        let data = PriceData { price, timestamp };
+        env.storage().persistent().set(&DataKey::Price(asset), &data);
+    }
+
+    /// Get the most recent price for an asset (SEP-40)
+    pub fn lastprice(env: Env, asset: Asset) -> Option<PriceData> {
+        env.storage().persistent().get(&DataKey::Price(asset))
+    }
+
+    /// Number of decimals in reported prices (SEP-40)
+    pub fn decimals(_env: Env) -> u32 {
+        7
+    }
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{Env, Address};
+use soroban_sdk::Env;
 use crate::storage::get_admin;
 
 pub fn require_admin(env: &Env) {
@@ -0,0 +1,52 @@
+#![no_std]
+
+use soroban_sdk::{contract, contractimpl, Env, Address, BytesN};
+
+mod admin;
+mod error;
+mod storage;
+
+pub use error::TokenError;
+
+#[contract]
+pub struct TokenContract;
+
+#[contractimpl]
+impl TokenContract {
+
+    pub fn initialize(env: Env, admin: Address) -> Result<(), TokenError> {
+        if storage::has_admin(&env) {
+            return Err(TokenError::AlreadyInitialized);
+        }
+        admin.require_auth();
+        storage::set_admin(&env, &admin);
+        Ok(())
+    }
+
+    pub fn mint(env: Env, to: Address, amount: i128) -> Result<(), TokenError> {
+        admin::require_admin(&env);
+
+        // checked arithmetic
+        let balance = storage::balance_of(&env, &to);
+        let new_balance = balance.checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+
+        storage::set_balance(&env, &to, &new_balance);
+        Ok(())
+    }
+
+    pub fn balance(env: Env, id: Address) -> i128 {
+        storage::balance_of(&env, &id)
+    }
+
+    /// Install new contract code; the admin is typically the governance contract
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) -> Result<(), TokenError> {
+        if admin != storage::get_admin(&env) {
+            return Err(TokenError::Unauthorized);
+        }
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(())
+    }
+}
@@ -1,6 +1,16 @@
-use soroban_sdk::{Env, Address, Symbol};
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol};
 
-const ADMIN_KEY: Symbol = Symbol::short("ADMIN");
+const ADMIN_KEY: Symbol = symbol_short!("ADMIN");
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Balance(Address),
+}
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&ADMIN_KEY)
+}
 
 pub fn set_admin(env: &Env, admin: &Address) {
     env.storage().instance().set(&ADMIN_KEY, admin);
@@ -12,3 +22,16 @@ pub fn get_admin(env: &Env) -> Address {
         .get(&ADMIN_KEY)
         .expect("Admin not set")
 }
+
+pub fn balance_of(env: &Env, account: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Balance(account.clone()))
+        .unwrap_or(0)
+}
+
+pub fn set_balance(env: &Env, account: &Address, balance: &i128) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::Balance(account.clone()), balance);
+}
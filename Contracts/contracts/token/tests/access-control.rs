@@ -1,16 +1,34 @@
-use soroban_sdk::{Env, testutils::Address as _};
-use token::TokenContract;
+use soroban_sdk::{testutils::Address as _, Address, Env};
+use token::{TokenContract, TokenContractClient, TokenError};
 
 #[test]
-#[should_panic(expected = "Unauthorized")]
-fn non_admin_cannot_mint() {
+fn mint_requires_admin_signature() {
     let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, TokenContract);
+    let client = TokenContractClient::new(&env, &contract_id);
 
-    let admin = Address::random(&env);
-    let attacker = Address::random(&env);
+    let admin = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    client.initialize(&admin);
 
-    TokenContract::initialize(env.clone(), admin);
+    // minting to anyone needs the stored admin's signature
+    client.mint(&recipient, &100);
+    assert_eq!(env.auths()[0].0, admin);
+    assert_eq!(client.balance(&recipient), 100);
+}
+
+#[test]
+fn initialize_only_once() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, TokenContract);
+    let client = TokenContractClient::new(&env, &contract_id);
 
-    // attacker tries mint
-    TokenContract::mint(env, attacker, 100);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    assert_eq!(
+        client.try_initialize(&Address::generate(&env)),
+        Err(Ok(TokenError::AlreadyInitialized))
+    );
 }
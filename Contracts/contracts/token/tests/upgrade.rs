@@ -0,0 +1,42 @@
+use soroban_sdk::{testutils::Address as _, Address, Env, Symbol};
+use token::{TokenContract, TokenContractClient, TokenError};
+
+const WASM_V1: &[u8] = include_bytes!("../testdata/version_v1.wasm");
+
+fn setup(env: &Env) -> (TokenContractClient<'_>, Address) {
+    let contract_id = env.register_contract(None, TokenContract);
+    let client = TokenContractClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    client.initialize(&admin);
+
+    (client, admin)
+}
+
+#[test]
+fn admin_upgrades_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin) = setup(&env);
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+
+    client.upgrade(&admin, &v1);
+    assert_eq!(env.auths()[0].0, admin);
+
+    let version = Symbol::new(&env, "version");
+    let installed: u32 = env.invoke_contract(&client.address, &version, soroban_sdk::vec![&env]);
+    assert_eq!(installed, 1);
+}
+
+#[test]
+fn only_admin_can_upgrade() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _) = setup(&env);
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+
+    let stranger = Address::generate(&env);
+    assert_eq!(client.try_upgrade(&stranger, &v1), Err(Ok(TokenError::Unauthorized)));
+}
@@ -0,0 +1,52 @@
+use soroban_sdk::{contracttype, Symbol};
+
+use crate::{TradeBounds, TradeError, TradeFee};
+
+/// Maximum number of items accepted in a single batch
+pub const MAX_BATCH_SIZE: u32 = 50;
+
+/// One trade within a batch, with the same parameters as `trade()`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TradeRequest {
+    pub pair: Symbol,
+    pub amount: i128,
+    pub price: i128,
+    pub is_buy: bool,
+    pub fee: TradeFee,
+    pub bounds: TradeBounds, // `TradeBounds::unbounded()` skips the checks
+}
+
+/// How a batch handles a failing item
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum BatchMode {
+    AllOrNothing = 0, // Revert the whole batch on the first failure
+    BestEffort = 1,   // Skip failing items and report their errors
+}
+
+/// Result of a single batch item
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BatchOutcome {
+    Ok(u64),  // Trade or order id
+    Err(u32), // `TradeError` code
+}
+
+pub fn validate_size(len: u32) -> Result<(), TradeError> {
+    if len == 0 || len > MAX_BATCH_SIZE {
+        return Err(TradeError::InvalidBatch);
+    }
+
+    Ok(())
+}
+
+/// Turn an item result into its outcome, or stop the batch in all-or-nothing mode
+pub fn outcome(mode: BatchMode, result: Result<u64, TradeError>) -> Result<BatchOutcome, TradeError> {
+    match result {
+        Ok(id) => Ok(BatchOutcome::Ok(id)),
+        Err(err) if mode == BatchMode::BestEffort => Ok(BatchOutcome::Err(err as u32)),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,130 @@
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol};
+use shared::fees::BPS_DENOMINATOR;
+
+use crate::{DataKey, TradeError};
+
+/// Circuit breaker thresholds for a trading pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitBreakerConfig {
+    pub max_move_bps: u32,   // Largest move from the reference price allowed inside a window
+    pub window: u64,         // Length of the reference window in seconds
+    pub cooldown: u64,       // How long the pair stays halted after a trip
+}
+
+/// Circuit breaker state for a trading pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairCircuit {
+    pub reference_price: i128, // First trade price of the current window
+    pub window_start: u64,
+    pub halted_until: u64,     // 0 when the pair is not halted
+}
+
+/// Circuit breaker trip event for off-chain indexing
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct CircuitTripEvent {
+    pub pair: Symbol,
+    pub reference_price: i128,
+    pub trade_price: i128,
+    pub halted_until: u64,
+}
+
+pub fn validate_config(config: &CircuitBreakerConfig) -> Result<(), TradeError> {
+    if config.max_move_bps == 0
+        || config.max_move_bps > BPS_DENOMINATOR
+        || config.window == 0
+        || config.cooldown == 0
+    {
+        return Err(TradeError::InvalidCircuitConfig);
+    }
+
+    Ok(())
+}
+
+pub fn get_state(env: &Env, pair: &Symbol) -> Option<PairCircuit> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::CircuitState(pair.clone()))
+}
+
+/// Reject trades on a halted pair
+pub fn require_not_halted(env: &Env, pair: &Symbol) -> Result<(), TradeError> {
+    if let Some(state) = get_state(env, pair) {
+        if env.ledger().timestamp() < state.halted_until {
+            return Err(TradeError::PairHalted);
+        }
+    }
+
+    Ok(())
+}
+
+/// Record a trade price and trip the breaker if it moved too far.
+///
+/// The trade that breaches the threshold still executes; the pair is halted
+/// for every trade after it until the cooldown ends.
+pub fn record_price(env: &Env, pair: &Symbol, price: i128) {
+    let config: CircuitBreakerConfig = match env
+        .storage()
+        .persistent()
+        .get(&DataKey::CircuitConfig(pair.clone()))
+    {
+        Some(config) => config,
+        None => return,
+    };
+
+    let now = env.ledger().timestamp();
+    let state = get_state(env, pair);
+
+    // Start a new window on the first trade, after a halt, or once the window lapses
+    let mut state = match state {
+        Some(state)
+            if state.reference_price > 0
+                && state.halted_until == 0
+                && now - state.window_start <= config.window =>
+        {
+            state
+        }
+        _ => {
+            let state = PairCircuit {
+                reference_price: price,
+                window_start: now,
+                halted_until: 0,
+            };
+            env.storage()
+                .persistent()
+                .set(&DataKey::CircuitState(pair.clone()), &state);
+            return;
+        }
+    };
+
+    let moved = (price - state.reference_price).abs();
+    if moved * BPS_DENOMINATOR as i128 > state.reference_price * config.max_move_bps as i128 {
+        state.halted_until = now + config.cooldown;
+
+        env.events().publish(
+            (symbol_short!("cb_trip"), pair.clone()),
+            CircuitTripEvent {
+                pair: pair.clone(),
+                reference_price: state.reference_price,
+                trade_price: price,
+                halted_until: state.halted_until,
+            },
+        );
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::CircuitState(pair.clone()), &state);
+    }
+}
+
+/// Clear a pair's halt and reference price
+pub fn reset(env: &Env, pair: &Symbol, admin: &Address) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::CircuitState(pair.clone()));
+
+    env.events()
+        .publish((symbol_short!("cb_reset"), pair.clone()), admin.clone());
+}
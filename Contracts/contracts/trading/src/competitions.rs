@@ -0,0 +1,296 @@
+use soroban_sdk::{contracttype, symbol_short, token, Address, Env, Symbol, Vec};
+use shared::fees::BPS_DENOMINATOR;
+
+use crate::{DataKey, TradeError};
+
+/// Most competitions that can run (or await finalization) at once
+pub const MAX_ACTIVE_COMPETITIONS: u32 = 5;
+
+/// Most traders that can register for one competition
+pub const MAX_PARTICIPANTS: u32 = 100;
+
+/// How competition scores are computed
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum ScoringRule {
+    Pnl = 0,    // Realized PnL on eligible pairs during the competition
+    Volume = 1, // Traded amount on eligible pairs during the competition
+}
+
+/// Parameters supplied when creating a competition
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompetitionConfig {
+    pub name: Symbol,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub pairs: Vec<Symbol>,      // Eligible pairs
+    pub scoring: ScoringRule,
+    pub prize_token: Address,
+    pub prize_pool: i128,        // Escrowed from the creator at creation
+    pub payout_bps: Vec<u32>,    // Prize share per rank, first place first
+    pub max_participants: u32,
+}
+
+/// Trading competition
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Competition {
+    pub id: u64,
+    pub creator: Address,
+    pub config: CompetitionConfig,
+    pub participants: u32,
+    pub finalized: bool,
+}
+
+/// Leaderboard row
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LeaderboardEntry {
+    pub trader: Address,
+    pub score: i128,
+}
+
+/// Prize paid to a ranked trader when a competition is finalized
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrizePayout {
+    pub rank: u32, // 1 for first place
+    pub trader: Address,
+    pub amount: i128,
+}
+
+pub fn get_competition(env: &Env, id: u64) -> Option<Competition> {
+    env.storage().persistent().get(&DataKey::Competition(id))
+}
+
+pub fn get_leaderboard(env: &Env, id: u64) -> Vec<LeaderboardEntry> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Leaderboard(id))
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn get_score(env: &Env, id: u64, trader: &Address) -> Option<i128> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::CompetitionScore(id, trader.clone()))
+}
+
+fn get_active(env: &Env) -> Vec<u64> {
+    env.storage()
+        .persistent()
+        .get(&symbol_short!("comps"))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Create a competition and escrow its prize pool from the creator
+pub fn create(env: &Env, creator: &Address, config: CompetitionConfig) -> Result<u64, TradeError> {
+    // An overflowing total is over 100% too
+    let share_total = config
+        .payout_bps
+        .iter()
+        .try_fold(0u32, u32::checked_add)
+        .unwrap_or(u32::MAX);
+    if config.start_time >= config.end_time
+        || config.end_time <= env.ledger().timestamp()
+        || config.pairs.is_empty()
+        || config.prize_pool < 0
+        || config.payout_bps.is_empty()
+        || share_total > BPS_DENOMINATOR
+        || config.max_participants == 0
+        || config.max_participants > MAX_PARTICIPANTS
+    {
+        return Err(TradeError::InvalidCompetition);
+    }
+
+    let mut active = get_active(env);
+    if active.len() >= MAX_ACTIVE_COMPETITIONS {
+        return Err(TradeError::InvalidCompetition);
+    }
+
+    if config.prize_pool > 0 {
+        token::Client::new(env, &config.prize_token).transfer(
+            creator,
+            &env.current_contract_address(),
+            &config.prize_pool,
+        );
+    }
+
+    let counter_key = symbol_short!("comp_cnt");
+    let id: u64 = env.storage().persistent().get(&counter_key).unwrap_or(0u64) + 1;
+
+    let competition = Competition {
+        id,
+        creator: creator.clone(),
+        config,
+        participants: 0,
+        finalized: false,
+    };
+
+    active.push_back(id);
+    env.storage().persistent().set(&symbol_short!("comps"), &active);
+    env.storage().persistent().set(&DataKey::Competition(id), &competition);
+    env.storage().persistent().set(&counter_key, &id);
+
+    env.events()
+        .publish((symbol_short!("comp_new"), id), competition.config.name);
+
+    Ok(id)
+}
+
+/// Register a trader for a competition that hasn't ended
+pub fn join(env: &Env, id: u64, trader: &Address) -> Result<(), TradeError> {
+    let mut competition = get_competition(env, id).ok_or(TradeError::CompetitionNotFound)?;
+
+    if competition.finalized || env.ledger().timestamp() >= competition.config.end_time {
+        return Err(TradeError::CompetitionClosed);
+    }
+    if get_score(env, id, trader).is_some() {
+        return Err(TradeError::AlreadyRegistered);
+    }
+    if competition.participants >= competition.config.max_participants {
+        return Err(TradeError::CompetitionFull);
+    }
+
+    competition.participants += 1;
+
+    let mut leaderboard = get_leaderboard(env, id);
+    leaderboard.push_back(LeaderboardEntry {
+        trader: trader.clone(),
+        score: 0,
+    });
+    sort_leaderboard(&mut leaderboard);
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::CompetitionScore(id, trader.clone()), &0i128);
+    env.storage().persistent().set(&DataKey::Leaderboard(id), &leaderboard);
+    env.storage().persistent().set(&DataKey::Competition(id), &competition);
+
+    env.events()
+        .publish((symbol_short!("comp_join"), id), trader.clone());
+
+    Ok(())
+}
+
+/// Update the scores of every running competition the trade counts towards
+pub fn record_trade(env: &Env, trader: &Address, pair: &Symbol, amount: i128, pnl: i128) {
+    let now = env.ledger().timestamp();
+
+    for id in get_active(env).iter() {
+        let competition = match get_competition(env, id) {
+            Some(competition) => competition,
+            None => continue,
+        };
+
+        if now < competition.config.start_time
+            || now >= competition.config.end_time
+            || !competition.config.pairs.contains(pair)
+        {
+            continue;
+        }
+
+        let score_key = DataKey::CompetitionScore(id, trader.clone());
+        let score: i128 = match env.storage().persistent().get(&score_key) {
+            Some(score) => score,
+            None => continue, // Not registered
+        };
+
+        let score = match competition.config.scoring {
+            ScoringRule::Pnl => score + pnl,
+            ScoringRule::Volume => score + amount,
+        };
+        env.storage().persistent().set(&score_key, &score);
+
+        let mut leaderboard = get_leaderboard(env, id);
+        if let Some(index) = leaderboard.iter().position(|entry| entry.trader == *trader) {
+            leaderboard.set(
+                index as u32,
+                LeaderboardEntry {
+                    trader: trader.clone(),
+                    score,
+                },
+            );
+        }
+        sort_leaderboard(&mut leaderboard);
+        env.storage().persistent().set(&DataKey::Leaderboard(id), &leaderboard);
+    }
+}
+
+/// Pay out the prize pool by rank once the competition has ended.
+///
+/// Rank `i` receives `payout_bps[i]` of the pool; shares for empty ranks and
+/// any remainder go back to the creator.
+pub fn finalize(env: &Env, id: u64) -> Result<Vec<PrizePayout>, TradeError> {
+    let mut competition = get_competition(env, id).ok_or(TradeError::CompetitionNotFound)?;
+
+    if competition.finalized {
+        return Err(TradeError::CompetitionFinalized);
+    }
+    if env.ledger().timestamp() < competition.config.end_time {
+        return Err(TradeError::CompetitionNotEnded);
+    }
+
+    let pool = competition.config.prize_pool;
+    let prize_token = token::Client::new(env, &competition.config.prize_token);
+    let contract = env.current_contract_address();
+    let leaderboard = get_leaderboard(env, id);
+
+    let mut payouts = Vec::new(env);
+    let mut paid: i128 = 0;
+    for (rank, share_bps) in competition.config.payout_bps.iter().enumerate() {
+        let entry = match leaderboard.get(rank as u32) {
+            Some(entry) => entry,
+            None => break,
+        };
+
+        let prize = pool * share_bps as i128 / BPS_DENOMINATOR as i128;
+        if prize > 0 {
+            prize_token.transfer(&contract, &entry.trader, &prize);
+            paid += prize;
+        }
+        payouts.push_back(PrizePayout {
+            rank: rank as u32 + 1,
+            trader: entry.trader,
+            amount: prize,
+        });
+    }
+
+    if pool - paid > 0 {
+        prize_token.transfer(&contract, &competition.creator, &(pool - paid));
+    }
+
+    competition.finalized = true;
+    env.storage().persistent().set(&DataKey::Competition(id), &competition);
+
+    let mut active = get_active(env);
+    if let Some(index) = active.first_index_of(id) {
+        active.remove(index);
+    }
+    env.storage().persistent().set(&symbol_short!("comps"), &active);
+
+    env.events()
+        .publish((symbol_short!("comp_end"), id), payouts.clone());
+
+    Ok(payouts)
+}
+
+/// Highest score first; tied entries keep their current order
+fn sort_leaderboard(leaderboard: &mut Vec<LeaderboardEntry>) {
+    // Insertion sort: the board is small and at most one entry is out of place
+    for i in 1..leaderboard.len() {
+        let mut j = i;
+        while j > 0 {
+            let prev = leaderboard.get_unchecked(j - 1);
+            let cur = leaderboard.get_unchecked(j);
+            if prev.score >= cur.score {
+                break;
+            }
+            leaderboard.set(j - 1, cur);
+            leaderboard.set(j, prev);
+            j -= 1;
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol};
+
+use crate::{DataKey, TradeError};
+
+/// Compliance attestation held by a trader
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Attestation {
+    pub issuer: Address,      // Compliance officer who issued it
+    pub reference: Symbol,    // Off-chain KYC record reference
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+pub fn get_attestation(env: &Env, trader: &Address) -> Option<Attestation> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Attestation(trader.clone()))
+}
+
+pub fn is_restricted(env: &Env, pair: &Symbol) -> bool {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PairRestricted(pair.clone()))
+        .unwrap_or(false)
+}
+
+pub fn set_restricted(env: &Env, pair: &Symbol, restricted: bool) {
+    let key = DataKey::PairRestricted(pair.clone());
+    if restricted {
+        env.storage().persistent().set(&key, &true);
+    } else {
+        env.storage().persistent().remove(&key);
+    }
+}
+
+/// Record an attestation for a trader, replacing any previous one
+pub fn attest(
+    env: &Env,
+    issuer: &Address,
+    trader: &Address,
+    reference: Symbol,
+    expires_at: u64,
+) -> Result<(), TradeError> {
+    let now = env.ledger().timestamp();
+    if expires_at <= now {
+        return Err(TradeError::InvalidAttestation);
+    }
+
+    let attestation = Attestation {
+        issuer: issuer.clone(),
+        reference,
+        issued_at: now,
+        expires_at,
+    };
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::Attestation(trader.clone()), &attestation);
+
+    env.events()
+        .publish((symbol_short!("attest"), trader.clone()), attestation);
+
+    Ok(())
+}
+
+pub fn revoke(env: &Env, issuer: &Address, trader: &Address) -> Result<(), TradeError> {
+    let key = DataKey::Attestation(trader.clone());
+    if !env.storage().persistent().has(&key) {
+        return Err(TradeError::AttestationRequired);
+    }
+
+    env.storage().persistent().remove(&key);
+
+    env.events()
+        .publish((symbol_short!("att_rev"), trader.clone()), issuer.clone());
+
+    Ok(())
+}
+
+/// Reject trades on restricted pairs by traders without a valid attestation
+pub fn require_attestation(env: &Env, pair: &Symbol, trader: &Address) -> Result<(), TradeError> {
+    if !is_restricted(env, pair) {
+        return Ok(());
+    }
+
+    let attestation = get_attestation(env, trader).ok_or(TradeError::AttestationRequired)?;
+    if env.ledger().timestamp() >= attestation.expires_at {
+        return Err(TradeError::AttestationExpired);
+    }
+
+    Ok(())
+}
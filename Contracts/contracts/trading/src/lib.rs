@@ -1,12 +1,46 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, Symbol, symbol_short, symbol};
-use shared::fees::{FeeManager, FeeError};
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, Address, BytesN, Env, Symbol, Val, symbol_short};
+use shared::fees::{FeeManager, FeeError, FeeMode, BPS_DENOMINATOR};
+use shared::oracle::{Asset, OracleError, OracleManager};
 use shared::governance::{
-    GovernanceManager, GovernanceError, GovernanceRole, UpgradeProposal, ProposalStatus,
+    GovernanceConfig, GovernanceManager, GovernanceError, GovernanceRole, Proposal, ProposalAction, RoleChange,
+    UpgradeProposal, ProposalStatus,
 };
+use shared::governance::voting::{
+    TokenVoting, VoteReceipt, VoteType, VotingConfig, VotingProposal, VotingState,
+};
+
+mod batch;
+mod circuit_breaker;
+mod competitions;
+mod compliance;
+mod limits;
+mod market_stats;
+mod migrations;
+mod orders;
+mod positions;
+mod schedules;
+mod simulation;
+
+pub use batch::{BatchMode, BatchOutcome, TradeRequest, MAX_BATCH_SIZE};
+pub use circuit_breaker::{CircuitBreakerConfig, CircuitTripEvent, PairCircuit};
+pub use compliance::Attestation;
+pub use competitions::{
+    Competition, CompetitionConfig, LeaderboardEntry, PrizePayout, ScoringRule,
+    MAX_ACTIVE_COMPETITIONS, MAX_PARTICIPANTS,
+};
+pub use limits::{TraderLimits, TraderUsage};
+pub use market_stats::{Candle, Interval, PairStats, MAX_CANDLE_RANGE};
+pub use migrations::MigrationError;
+pub use orders::{
+    ConditionalOrder, OrderKind, OrderRequest, OrderStatus, OrderTriggeredEvent, TriggerSource,
+};
+pub use positions::{Position, TraderStats};
+pub use schedules::{Schedule, ScheduleRequest, ScheduleStatus};
+pub use simulation::SimAccount;
 
 /// Version of this contract implementation
-const CONTRACT_VERSION: u32 = 1;
+pub(crate) const CONTRACT_VERSION: u32 = 7;
 
 /// Trading contract with upgradeability and governance
 #[contract]
@@ -14,7 +48,7 @@ pub struct UpgradeableTradingContract;
 
 /// Trade record for tracking
 #[contracttype]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Trade {
     pub id: u64,
     pub trader: Address,
@@ -34,6 +68,87 @@ pub struct TradeStats {
     pub last_trade_id: u64,
 }
 
+/// Fee charged on a trade
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TradeFee {
+    pub token: Address,
+    pub amount: i128,
+    pub recipient: Address,
+}
+
+/// Slippage and expiry protection for a trade.
+///
+/// A buy takes in `amount * price` quote and pays out `amount` base; a sell
+/// takes in `amount` base and pays out `amount * price` quote.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TradeBounds {
+    pub min_out: i128,   // Least the trader accepts to receive
+    pub max_in: i128,    // Most the trader accepts to pay, 0 for no limit
+    pub deadline: u64,   // Last timestamp the trade may execute at
+}
+
+impl TradeBounds {
+    /// Bounds that accept any price at any time
+    pub fn unbounded() -> Self {
+        TradeBounds {
+            min_out: 0,
+            max_in: 0,
+            deadline: u64::MAX,
+        }
+    }
+}
+
+/// Lifetime statistics for a referrer
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferralStats {
+    pub referred_traders: u32,
+    pub referred_trades: u64,
+    pub referred_volume: i128,
+}
+
+/// Storage keys for per-address trading state
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Referrer(Address),                 // Referrer registered for a trader
+    ReferralStats(Address),            // Lifetime stats per referrer
+    ReferralRewards(Address, Address), // Rewards per (referrer, fee token)
+    PairOracle(Symbol),                // Oracle price band per pair
+    CircuitConfig(Symbol),             // Circuit breaker thresholds per pair
+    CircuitState(Symbol),              // Circuit breaker reference price and halt per pair
+    Position(Address, Symbol),         // Position per (trader, pair)
+    TraderStats(Address),              // Totals per trader
+    PairStats(Symbol),                 // Totals per pair
+    Candle(Symbol, Interval, u64),     // OHLCV per (pair, interval, bucket index)
+    SimAccount(Address),               // Paper-trading account per learner
+    SimPosition(Address, Symbol),      // Simulated position per (learner, pair)
+    SimTrade(u64),                     // Simulated trade record
+    Competition(u64),                  // Competition by id
+    CompetitionScore(u64, Address),    // Score per (competition, registered trader)
+    Leaderboard(u64),                  // Sorted scores per competition
+    Order(u64),                        // Conditional order by id
+    Schedule(u64),                     // Recurring TWAP/DCA schedule by id
+    TraderLimits(Address),             // Limit override per trader
+    TraderUsage(Address),              // Usage counted against limits per trader
+    Attestation(Address),              // Compliance attestation per trader
+    PairRestricted(Symbol),            // Pairs that require an attestation to trade
+    Trade(u64),                        // Trade record by id
+}
+
+/// Oracle guard for a trading pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairOracle {
+    pub oracle: Address,          // SEP-40 oracle contract
+    pub asset: Asset,             // Asset the oracle prices for this pair
+    pub max_deviation_bps: u32,   // Allowed distance of trade price from oracle price
+    pub max_age: u64,             // Maximum oracle price age in seconds
+}
+
+#[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum TradeError {
@@ -41,6 +156,74 @@ pub enum TradeError {
     InvalidAmount = 3002,
     ContractPaused = 3003,
     NotInitialized = 3004,
+    SelfReferral = 3005,
+    ReferrerAlreadySet = 3006,
+    InvalidFeeShare = 3007,
+    InsufficientBalance = 3008,
+    OracleUnavailable = 3009,
+    StalePrice = 3010,
+    PriceDeviation = 3011,
+    InvalidOracleConfig = 3012,
+    PairHalted = 3013,
+    InvalidCircuitConfig = 3014,
+    InvalidBucketRange = 3015,
+    SimAccountNotFound = 3016,
+    SimAccountExists = 3017,
+    InsufficientSimBalance = 3018,
+    CompetitionNotFound = 3019,
+    InvalidCompetition = 3020,
+    CompetitionClosed = 3021,
+    AlreadyRegistered = 3022,
+    CompetitionFull = 3023,
+    CompetitionNotEnded = 3024,
+    CompetitionFinalized = 3025,
+    OrderNotFound = 3026,
+    InvalidOrder = 3027,
+    OrderNotOpen = 3028,
+    OrderExpired = 3029,
+    OrderNotExpired = 3030,
+    TriggerNotReached = 3031,
+    LimitPriceNotMet = 3032,
+    NoReferencePrice = 3033,
+    ScheduleNotFound = 3034,
+    InvalidSchedule = 3035,
+    ScheduleNotActive = 3036,
+    SliceNotDue = 3037,
+    ScheduleEnded = 3038,
+    TradeRateLimited = 3039,
+    DailyNotionalExceeded = 3040,
+    TooManyOpenOrders = 3041,
+    InvalidLimitConfig = 3042,
+    AttestationRequired = 3043,
+    AttestationExpired = 3044,
+    InvalidAttestation = 3045,
+    RoleAlreadyAssigned = 3046,
+    DeadlineExpired = 3047,
+    InsufficientOutput = 3048,
+    ExcessiveInput = 3049,
+    InvalidBatch = 3050,
+}
+
+impl From<FeeError> for TradeError {
+    fn from(err: FeeError) -> Self {
+        match err {
+            FeeError::InsufficientBalance => TradeError::InsufficientBalance,
+            FeeError::InvalidShare => TradeError::InvalidFeeShare,
+            FeeError::InvalidAmount | FeeError::NothingToWithdraw => TradeError::InvalidAmount,
+        }
+    }
+}
+
+impl From<OracleError> for TradeError {
+    fn from(err: OracleError) -> Self {
+        match err {
+            OracleError::OracleUnavailable | OracleError::PriceNotFound => {
+                TradeError::OracleUnavailable
+            }
+            OracleError::StalePrice => TradeError::StalePrice,
+            OracleError::PriceDeviation => TradeError::PriceDeviation,
+        }
+    }
 }
 
 #[contractimpl]
@@ -61,22 +244,16 @@ impl UpgradeableTradingContract {
         // Set initialization flag
         env.storage().persistent().set(&init_key, &true);
 
-        // Store roles
-        let roles_key = symbol_short!("roles");
-        let mut roles = soroban_sdk::Map::new(&env);
-
         // Set admin role
-        roles.set(admin, GovernanceRole::Admin);
+        GovernanceManager::assign_role(&env, admin, GovernanceRole::Admin);
 
         // Set approvers
         for approver in approvers.iter() {
-            roles.set(approver, GovernanceRole::Approver);
+            GovernanceManager::assign_role(&env, approver, GovernanceRole::Approver);
         }
 
         // Set executor
-        roles.set(executor, GovernanceRole::Executor);
-
-        env.storage().persistent().set(&roles_key, &roles);
+        GovernanceManager::assign_role(&env, executor, GovernanceRole::Executor);
 
         // Initialize stats
         let stats = TradeStats {
@@ -94,7 +271,9 @@ impl UpgradeableTradingContract {
         Ok(())
     }
 
-    /// Execute a trade with fee collection
+    /// Execute a trade with fee collection. When `bounds` are given the trade
+    /// reverts if it would execute after the deadline or at a worse price.
+    #[allow(clippy::too_many_arguments)]
     pub fn trade(
         env: Env,
         trader: Address,
@@ -105,223 +284,1387 @@ impl UpgradeableTradingContract {
         fee_token: Address,
         fee_amount: i128,
         fee_recipient: Address,
-    ) -> Result<u64, FeeError> {
+        bounds: Option<TradeBounds>,
+    ) -> Result<u64, TradeError> {
         trader.require_auth();
 
-        // Verify not paused
-        let paused_key = symbol_short!("pause");
-        let is_paused: bool = env
-            .storage()
-            .persistent()
-            .get(&paused_key)
-            .unwrap_or(false);
-
-        if is_paused {
-            env.panic_with_error(symbol_short!("PAUSED"));
-        }
-
-        // Collect fee first
-        FeeManager::collect_fee(&env, &fee_token, &trader, &fee_recipient, fee_amount)?;
-
-        // Create trade record
-        let stats_key = symbol_short!("stats");
-        let mut stats: TradeStats = env
-            .storage()
-            .persistent()
-            .get(&stats_key)
-            .unwrap_or(TradeStats {
-                total_trades: 0,
-                total_volume: 0,
-                last_trade_id: 0,
-            });
-
-        let trade_id = stats.last_trade_id + 1;
-        let trade = Trade {
-            id: trade_id,
-            trader,
+        let request = TradeRequest {
             pair,
             amount,
             price,
-            timestamp: env.ledger().timestamp(),
             is_buy,
+            fee: TradeFee {
+                token: fee_token,
+                amount: fee_amount,
+                recipient: fee_recipient,
+            },
+            bounds: bounds.unwrap_or_else(TradeBounds::unbounded),
         };
 
-        // Update stats
-        stats.total_trades += 1;
-        stats.total_volume += amount;
-        stats.last_trade_id = trade_id;
-
-        // Store trade
-        let trades_key = symbol_short!("trades");
-        let mut trades: soroban_sdk::Vec<Trade> = env
-            .storage()
-            .persistent()
-            .get(&trades_key)
-            .unwrap_or_else(|_| soroban_sdk::Vec::new(&env));
+        Self::submit_trade(&env, &trader, &request)
+    }
 
-        trades.push_back(trade);
+    /// Execute several trades under a single authorization. In best-effort
+    /// mode failing trades are skipped without side effects and reported
+    /// with their error code.
+    pub fn trade_batch(
+        env: Env,
+        trader: Address,
+        requests: soroban_sdk::Vec<TradeRequest>,
+        mode: BatchMode,
+    ) -> Result<soroban_sdk::Vec<BatchOutcome>, TradeError> {
+        trader.require_auth();
+        batch::validate_size(requests.len())?;
 
-        // Update persistent storage
-        env.storage().persistent().set(&trades_key, &trades);
-        env.storage().persistent().set(&stats_key, &stats);
+        let mut outcomes = soroban_sdk::Vec::new(&env);
+        for request in requests.iter() {
+            let result = Self::submit_trade(&env, &trader, &request);
+            outcomes.push_back(batch::outcome(mode, result)?);
+        }
 
-        Ok(trade_id)
+        Ok(outcomes)
     }
 
-    /// Get current contract version
-    pub fn get_version(env: Env) -> u32 {
-        let version_key = symbol_short!("ver");
-        env.storage()
-            .persistent()
-            .get(&version_key)
-            .unwrap_or(0)
+    /// Open a paper-trading sandbox funded with virtual balance
+    pub fn sim_open_account(env: Env, learner: Address) -> Result<SimAccount, TradeError> {
+        learner.require_auth();
+
+        simulation::open_account(&env, &learner)
     }
 
-    /// Get trading statistics
-    pub fn get_stats(env: Env) -> TradeStats {
-        let stats_key = symbol_short!("stats");
-        env.storage()
-            .persistent()
-            .get(&stats_key)
-            .unwrap_or(TradeStats {
-                total_trades: 0,
-                total_volume: 0,
-                last_trade_id: 0,
-            })
+    /// Execute a simulated trade. Orders pass the same pricing checks as
+    /// `trade` but only move virtual balances and never touch `TradeStats`.
+    pub fn sim_trade(
+        env: Env,
+        learner: Address,
+        pair: Symbol,
+        amount: i128,
+        price: i128,
+        is_buy: bool,
+    ) -> Result<u64, TradeError> {
+        learner.require_auth();
+
+        Self::validate_trade(&env, &pair, amount, price)?;
+
+        simulation::execute(&env, &learner, &pair, amount, price, is_buy)
     }
 
-    /// Pause the contract (admin only)
-    pub fn pause(env: Env, admin: Address) -> Result<(), TradeError> {
+    /// Reset a learner's sandbox to the starting balance (admin only)
+    pub fn sim_reset(env: Env, admin: Address, learner: Address) -> Result<SimAccount, TradeError> {
         admin.require_auth();
+        Self::require_admin(&env, &admin)?;
 
-        // Verify admin role
-        let roles_key = symbol_short!("roles");
-        let roles: soroban_sdk::Map<Address, GovernanceRole> = env
-            .storage()
-            .persistent()
-            .get(&roles_key)
-            .ok_or(TradeError::Unauthorized)?;
+        simulation::reset_account(&env, &learner)
+    }
 
-        let role = roles
-            .get(admin)
-            .ok_or(TradeError::Unauthorized)?;
+    /// Set the virtual balance new and reset sandboxes start with (admin only)
+    pub fn set_sim_starting_balance(
+        env: Env,
+        admin: Address,
+        amount: i128,
+    ) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
 
-        if role != GovernanceRole::Admin {
-            return Err(TradeError::Unauthorized);
+        if amount <= 0 {
+            return Err(TradeError::InvalidAmount);
         }
 
-        let paused_key = symbol_short!("pause");
-        env.storage().persistent().set(&paused_key, &true);
+        env.storage()
+            .persistent()
+            .set(&symbol_short!("sim_bal"), &amount);
 
         Ok(())
     }
 
-    /// Unpause the contract (admin only)
-    pub fn unpause(env: Env, admin: Address) -> Result<(), TradeError> {
+    /// Get a learner's paper-trading account
+    pub fn get_sim_account(env: Env, learner: Address) -> Option<SimAccount> {
+        simulation::get_account(&env, &learner)
+    }
+
+    /// Get a learner's simulated position in a pair
+    pub fn get_sim_position(env: Env, learner: Address, pair: Symbol) -> Position {
+        simulation::get_position(&env, &learner, &pair)
+    }
+
+    /// Get statistics for simulated trades across all learners
+    pub fn get_sim_stats(env: Env) -> TradeStats {
+        simulation::get_stats(&env)
+    }
+
+    /// Create a trading competition and escrow its prize pool (admin only)
+    pub fn create_competition(
+        env: Env,
+        admin: Address,
+        config: CompetitionConfig,
+    ) -> Result<u64, TradeError> {
         admin.require_auth();
+        Self::require_admin(&env, &admin)?;
 
-        let roles_key = symbol_short!("roles");
-        let roles: soroban_sdk::Map<Address, GovernanceRole> = env
-            .storage()
-            .persistent()
-            .get(&roles_key)
-            .ok_or(TradeError::Unauthorized)?;
+        competitions::create(&env, &admin, config)
+    }
 
-        let role = roles
-            .get(admin)
-            .ok_or(TradeError::Unauthorized)?;
+    /// Register for a competition
+    pub fn join_competition(env: Env, trader: Address, competition_id: u64) -> Result<(), TradeError> {
+        trader.require_auth();
 
-        if role != GovernanceRole::Admin {
-            return Err(TradeError::Unauthorized);
-        }
+        competitions::join(&env, competition_id, &trader)
+    }
 
-        let paused_key = symbol_short!("pause");
-        env.storage().persistent().set(&paused_key, &false);
+    /// Pay out an ended competition's prize pool by rank. Anyone can call this.
+    pub fn finalize_competition(
+        env: Env,
+        competition_id: u64,
+    ) -> Result<soroban_sdk::Vec<PrizePayout>, TradeError> {
+        competitions::finalize(&env, competition_id)
+    }
 
-        Ok(())
+    /// Get a competition by ID
+    pub fn get_competition(env: Env, competition_id: u64) -> Option<Competition> {
+        competitions::get_competition(&env, competition_id)
     }
 
-    /// Propose an upgrade via governance
-    pub fn propose_upgrade(
+    /// Get a competition's leaderboard, highest score first
+    pub fn get_leaderboard(env: Env, competition_id: u64) -> soroban_sdk::Vec<LeaderboardEntry> {
+        competitions::get_leaderboard(&env, competition_id)
+    }
+
+    /// Get a registered trader's competition score
+    pub fn get_competition_score(env: Env, competition_id: u64, trader: Address) -> Option<i128> {
+        competitions::get_score(&env, competition_id, &trader)
+    }
+
+    /// Place a stop-loss, stop-limit or take-profit order. The fee and keeper
+    /// bounty are escrowed in the fee token until the order executes, is
+    /// cancelled, or expires.
+    pub fn place_order(env: Env, owner: Address, request: OrderRequest) -> Result<u64, TradeError> {
+        owner.require_auth();
+
+        orders::place(&env, &owner, request)
+    }
+
+    /// Cancel an open order and refund its escrow
+    pub fn cancel_order(env: Env, owner: Address, order_id: u64) -> Result<(), TradeError> {
+        owner.require_auth();
+
+        orders::cancel(&env, &owner, order_id)
+    }
+
+    /// Cancel several open orders under a single authorization
+    pub fn cancel_batch(
         env: Env,
-        admin: Address,
-        new_contract_hash: Symbol,
-        description: Symbol,
-        approvers: soroban_sdk::Vec<Address>,
-        approval_threshold: u32,
-        timelock_delay: u64,
-    ) -> Result<u64, TradeError> {
-        admin.require_auth();
+        owner: Address,
+        order_ids: soroban_sdk::Vec<u64>,
+        mode: BatchMode,
+    ) -> Result<soroban_sdk::Vec<BatchOutcome>, TradeError> {
+        owner.require_auth();
+        batch::validate_size(order_ids.len())?;
+
+        let mut outcomes = soroban_sdk::Vec::new(&env);
+        for order_id in order_ids.iter() {
+            let result = orders::cancel(&env, &owner, order_id).map(|_| order_id);
+            outcomes.push_back(batch::outcome(mode, result)?);
+        }
+
+        Ok(outcomes)
+    }
+
+    /// Execute a triggered order as a normal trade and pay the keeper's bounty
+    pub fn execute_order(env: Env, keeper: Address, order_id: u64) -> Result<u64, TradeError> {
+        keeper.require_auth();
+
+        let mut order = orders::get_order(&env, order_id).ok_or(TradeError::OrderNotFound)?;
+        if order.status != OrderStatus::Open {
+            return Err(TradeError::OrderNotOpen);
+        }
+        if env.ledger().timestamp() > order.request.expiry {
+            return Err(TradeError::OrderExpired);
+        }
+
+        let reference = orders::trigger_reference(&env, &order.request)?;
+        if !orders::is_triggered(&order.request, reference) {
+            return Err(TradeError::TriggerNotReached);
+        }
+        let price = orders::fill_price(&order.request, reference)?;
 
-        let proposal_result = GovernanceManager::propose_upgrade(
+        // The fee was escrowed at placement, so the contract pays it
+        let request = order.request.clone();
+        let trade_id = Self::execute_trade(
             &env,
-            admin,
-            new_contract_hash,
-            env.current_contract_address(),
-            description,
-            approval_threshold,
-            approvers,
-            timelock_delay,
-        );
+            &order.owner,
+            &env.current_contract_address(),
+            &request.pair,
+            request.amount,
+            price,
+            request.is_buy,
+            &request.fee,
+        )?;
+
+        orders::complete(&env, &mut order, &keeper, reference, trade_id);
 
-        match proposal_result {
-            Ok(id) => Ok(id),
-            Err(_) => Err(TradeError::Unauthorized),
+        Ok(trade_id)
+    }
+
+    /// Expire an order past its expiry and refund its escrow. Anyone can call this.
+    pub fn expire_order(env: Env, order_id: u64) -> Result<(), TradeError> {
+        let mut order = orders::get_order(&env, order_id).ok_or(TradeError::OrderNotFound)?;
+        if order.status != OrderStatus::Open {
+            return Err(TradeError::OrderNotOpen);
+        }
+        if env.ledger().timestamp() <= order.request.expiry {
+            return Err(TradeError::OrderNotExpired);
         }
+
+        orders::close(&env, &mut order, OrderStatus::Expired)
     }
 
-    /// Approve an upgrade proposal
-    pub fn approve_upgrade(
+    /// Get a conditional order by ID
+    pub fn get_order(env: Env, order_id: u64) -> Option<ConditionalOrder> {
+        orders::get_order(&env, order_id)
+    }
+
+    /// Create a recurring TWAP/DCA schedule. The fee and keeper bounty for
+    /// every slice are escrowed in the fee token up front.
+    pub fn create_schedule(
         env: Env,
-        proposal_id: u64,
-        approver: Address,
-    ) -> Result<(), TradeError> {
-        approver.require_auth();
+        owner: Address,
+        request: ScheduleRequest,
+    ) -> Result<u64, TradeError> {
+        owner.require_auth();
 
-        GovernanceManager::approve_proposal(&env, proposal_id, approver)
-            .map_err(|_| TradeError::Unauthorized)
+        schedules::create(&env, &owner, request)
     }
 
-    /// Execute an approved upgrade proposal
-    pub fn execute_upgrade(
-        env: Env,
-        proposal_id: u64,
-        executor: Address,
-    ) -> Result<(), TradeError> {
-        executor.require_auth();
+    /// Execute a schedule's next due slice as a normal trade and pay the keeper's bounty
+    pub fn execute_slice(env: Env, keeper: Address, schedule_id: u64) -> Result<u64, TradeError> {
+        keeper.require_auth();
 
-        GovernanceManager::execute_proposal(&env, proposal_id, executor)
-            .map_err(|_| TradeError::Unauthorized)
+        let mut schedule =
+            schedules::get_schedule(&env, schedule_id).ok_or(TradeError::ScheduleNotFound)?;
+        let (amount, price) = schedules::next_slice(&env, &schedule)?;
+
+        // The fee was escrowed at creation, so the contract pays it
+        let request = schedule.request.clone();
+        let trade_id = Self::execute_trade(
+            &env,
+            &schedule.owner,
+            &env.current_contract_address(),
+            &request.pair,
+            amount,
+            price,
+            request.is_buy,
+            &request.fee,
+        )?;
+
+        schedules::complete_slice(&env, &mut schedule, &keeper, amount, trade_id);
+
+        Ok(trade_id)
     }
 
-    /// Get upgrade proposal details
-    pub fn get_upgrade_proposal(env: Env, proposal_id: u64) -> Result<UpgradeProposal, TradeError> {
-        GovernanceManager::get_proposal(&env, proposal_id)
-            .map_err(|_| TradeError::Unauthorized)
+    /// Cancel a schedule and refund the escrow for its unexecuted slices
+    pub fn cancel_schedule(env: Env, owner: Address, schedule_id: u64) -> Result<i128, TradeError> {
+        owner.require_auth();
+
+        let mut schedule =
+            schedules::get_schedule(&env, schedule_id).ok_or(TradeError::ScheduleNotFound)?;
+        if schedule.owner != owner {
+            return Err(TradeError::Unauthorized);
+        }
+
+        schedules::cancel(&env, &mut schedule)
     }
 
-    /// Reject an upgrade proposal
-    pub fn reject_upgrade(
+    /// Get a recurring schedule by ID
+    pub fn get_schedule(env: Env, schedule_id: u64) -> Option<Schedule> {
+        schedules::get_schedule(&env, schedule_id)
+    }
+
+    /// Set the limits applied to traders without an override (admin only)
+    pub fn set_default_limits(
         env: Env,
-        proposal_id: u64,
-        rejector: Address,
+        admin: Address,
+        config: TraderLimits,
     ) -> Result<(), TradeError> {
-        rejector.require_auth();
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+        limits::validate_limits(&config)?;
 
-        GovernanceManager::reject_proposal(&env, proposal_id, rejector)
-            .map_err(|_| TradeError::Unauthorized)
+        env.storage()
+            .persistent()
+            .set(&symbol_short!("limits"), &config);
+
+        Ok(())
     }
 
-    /// Cancel an upgrade proposal (admin only)
-    pub fn cancel_upgrade(
+    /// Override the limits for a single trader (admin only)
+    pub fn set_trader_limits(
         env: Env,
-        proposal_id: u64,
         admin: Address,
+        trader: Address,
+        config: TraderLimits,
     ) -> Result<(), TradeError> {
         admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+        limits::validate_limits(&config)?;
 
-        GovernanceManager::cancel_proposal(&env, proposal_id, admin)
-            .map_err(|_| TradeError::Unauthorized)
+        env.storage()
+            .persistent()
+            .set(&DataKey::TraderLimits(trader), &config);
+
+        Ok(())
+    }
+
+    /// Remove a trader's override so the default limits apply again (admin only)
+    pub fn clear_trader_limits(env: Env, admin: Address, trader: Address) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::TraderLimits(trader));
+
+        Ok(())
+    }
+
+    /// Get the limits in effect for a trader
+    pub fn get_trader_limits(env: Env, trader: Address) -> TraderLimits {
+        limits::get_limits(&env, &trader)
+    }
+
+    /// Get a trader's usage in the current window and day
+    pub fn get_trader_usage(env: Env, trader: Address) -> TraderUsage {
+        limits::get_usage(&env, &trader)
+    }
+
+    /// Schedule granting the compliance role to an address (admin only).
+    /// Like any other role grant it takes effect once an executor runs the
+    /// returned change after the role-change timelock.
+    pub fn set_compliance_officer(
+        env: Env,
+        admin: Address,
+        officer: Address,
+    ) -> Result<u64, TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+
+        GovernanceManager::grant_role(&env, admin, GovernanceRole::Compliance, officer).map_err(
+            |err| match err {
+                GovernanceError::RoleAlreadyHeld => TradeError::RoleAlreadyAssigned,
+                _ => TradeError::Unauthorized,
+            },
+        )
+    }
+
+    /// Revoke the compliance role from an address (admin only). Removal is
+    /// immediate so a compromised officer can be cut off without waiting.
+    pub fn remove_compliance_officer(
+        env: Env,
+        admin: Address,
+        officer: Address,
+    ) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+        Self::require_role(&env, &officer, GovernanceRole::Compliance)?;
+
+        GovernanceManager::remove_role(&env, &officer, GovernanceRole::Compliance)
+            .map_err(|_| TradeError::Unauthorized)
+    }
+
+    /// Record that a trader passed compliance checks until `expires_at` (compliance role only)
+    pub fn attest(
+        env: Env,
+        officer: Address,
+        trader: Address,
+        reference: Symbol,
+        expires_at: u64,
+    ) -> Result<(), TradeError> {
+        officer.require_auth();
+        Self::require_role(&env, &officer, GovernanceRole::Compliance)?;
+
+        compliance::attest(&env, &officer, &trader, reference, expires_at)
+    }
+
+    /// Revoke a trader's attestation (compliance role only)
+    pub fn revoke_attestation(env: Env, officer: Address, trader: Address) -> Result<(), TradeError> {
+        officer.require_auth();
+        Self::require_role(&env, &officer, GovernanceRole::Compliance)?;
+
+        compliance::revoke(&env, &officer, &trader)
+    }
+
+    /// Get a trader's attestation
+    pub fn get_attestation(env: Env, trader: Address) -> Option<Attestation> {
+        compliance::get_attestation(&env, &trader)
+    }
+
+    /// Require a valid attestation to trade a pair (admin only)
+    pub fn set_pair_restricted(
+        env: Env,
+        admin: Address,
+        pair: Symbol,
+        restricted: bool,
+    ) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+
+        compliance::set_restricted(&env, &pair, restricted);
+
+        Ok(())
+    }
+
+    /// Check whether a pair requires an attestation
+    pub fn is_pair_restricted(env: Env, pair: Symbol) -> bool {
+        compliance::is_restricted(&env, &pair)
+    }
+
+    /// Get current contract version
+    pub fn get_version(env: Env) -> u32 {
+        migrations::get_version(&env)
+    }
+
+    /// Migrate storage written by `from_version` to the layout of this
+    /// implementation after an upgrade (executor only). Downgrades are refused.
+    pub fn migrate(env: Env, executor: Address, from_version: u32) -> Result<u32, MigrationError> {
+        executor.require_auth();
+        Self::require_role(&env, &executor, GovernanceRole::Executor)
+            .map_err(|_| MigrationError::Unauthorized)?;
+
+        migrations::migrate(&env, from_version)
+    }
+
+    /// Get a trade by ID
+    pub fn get_trade(env: Env, trade_id: u64) -> Option<Trade> {
+        env.storage().persistent().get(&DataKey::Trade(trade_id))
+    }
+
+    /// Get trading statistics
+    pub fn get_stats(env: Env) -> TradeStats {
+        let stats_key = symbol_short!("stats");
+        env.storage()
+            .persistent()
+            .get(&stats_key)
+            .unwrap_or(TradeStats {
+                total_trades: 0,
+                total_volume: 0,
+                last_trade_id: 0,
+            })
+    }
+
+    /// Register the referrer who brought a trader to the platform.
+    /// A trader can only be referred once and cannot refer themselves.
+    pub fn register_referrer(
+        env: Env,
+        trader: Address,
+        referrer: Address,
+    ) -> Result<(), TradeError> {
+        trader.require_auth();
+
+        if trader == referrer {
+            return Err(TradeError::SelfReferral);
+        }
+
+        let referrer_key = DataKey::Referrer(trader.clone());
+        if env.storage().persistent().has(&referrer_key) {
+            return Err(TradeError::ReferrerAlreadySet);
+        }
+
+        env.storage().persistent().set(&referrer_key, &referrer);
+
+        let ref_stats_key = DataKey::ReferralStats(referrer.clone());
+        let mut ref_stats = Self::load_referral_stats(&env, &ref_stats_key);
+        ref_stats.referred_traders += 1;
+        env.storage().persistent().set(&ref_stats_key, &ref_stats);
+
+        env.events()
+            .publish((symbol_short!("referral"),), (trader, referrer));
+
+        Ok(())
+    }
+
+    /// Set the share of trade fees paid to referrers, in basis points (admin only)
+    pub fn set_referral_share(env: Env, admin: Address, share_bps: u32) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+
+        if share_bps > BPS_DENOMINATOR {
+            return Err(TradeError::InvalidFeeShare);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&symbol_short!("ref_bps"), &share_bps);
+
+        Ok(())
+    }
+
+    /// Get the referral fee share in basis points
+    pub fn get_referral_share(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&symbol_short!("ref_bps"))
+            .unwrap_or(0)
+    }
+
+    /// Get the referrer registered for a trader
+    pub fn get_referrer(env: Env, trader: Address) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::Referrer(trader))
+    }
+
+    /// Get lifetime referral statistics for a referrer
+    pub fn get_referral_stats(env: Env, referrer: Address) -> ReferralStats {
+        Self::load_referral_stats(&env, &DataKey::ReferralStats(referrer))
+    }
+
+    /// Get the total referral rewards a referrer has earned in a fee token
+    pub fn get_referral_rewards(env: Env, referrer: Address, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ReferralRewards(referrer, token))
+            .unwrap_or(0)
+    }
+
+    /// Switch between direct fee transfers and accrual in the contract (admin only)
+    pub fn set_fee_mode(env: Env, admin: Address, mode: FeeMode) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+
+        FeeManager::set_mode(&env, mode);
+
+        Ok(())
+    }
+
+    /// Get how trade fees are delivered to recipients
+    pub fn get_fee_mode(env: Env) -> FeeMode {
+        FeeManager::get_mode(&env)
+    }
+
+    /// Withdraw all fees accrued to the recipient in a token
+    pub fn withdraw_fees(env: Env, recipient: Address, token: Address) -> Result<i128, FeeError> {
+        recipient.require_auth();
+
+        let amount = FeeManager::withdraw_fees(&env, &token, &recipient)?;
+
+        env.events()
+            .publish((symbol_short!("fee_wd"),), (recipient, token, amount));
+
+        Ok(amount)
+    }
+
+    /// Get fees accrued to a recipient in a token and not yet withdrawn
+    pub fn get_accrued_fees(env: Env, recipient: Address, token: Address) -> i128 {
+        FeeManager::accrued_fees(&env, &recipient, &token)
+    }
+
+    /// Get total fees held by the contract in a token for all recipients
+    pub fn get_total_accrued_fees(env: Env, token: Address) -> i128 {
+        FeeManager::total_accrued(&env, &token)
+    }
+
+    /// Guard a pair with an oracle price band (admin only)
+    pub fn set_pair_oracle(
+        env: Env,
+        admin: Address,
+        pair: Symbol,
+        config: PairOracle,
+    ) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+
+        if config.max_deviation_bps == 0
+            || config.max_deviation_bps > BPS_DENOMINATOR
+            || config.max_age == 0
+        {
+            return Err(TradeError::InvalidOracleConfig);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::PairOracle(pair), &config);
+
+        Ok(())
+    }
+
+    /// Remove the oracle guard from a pair (admin only)
+    pub fn remove_pair_oracle(env: Env, admin: Address, pair: Symbol) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+
+        env.storage().persistent().remove(&DataKey::PairOracle(pair));
+
+        Ok(())
+    }
+
+    /// Get the oracle guard configured for a pair
+    pub fn get_pair_oracle(env: Env, pair: Symbol) -> Option<PairOracle> {
+        env.storage().persistent().get(&DataKey::PairOracle(pair))
+    }
+
+    /// Configure the circuit breaker for a pair (admin only)
+    pub fn set_circuit_breaker(
+        env: Env,
+        admin: Address,
+        pair: Symbol,
+        config: CircuitBreakerConfig,
+    ) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+        circuit_breaker::validate_config(&config)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::CircuitConfig(pair), &config);
+
+        Ok(())
+    }
+
+    /// Lift a circuit breaker halt and clear the pair's reference price (admin only)
+    pub fn reset_circuit_breaker(env: Env, admin: Address, pair: Symbol) -> Result<(), TradeError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin)?;
+
+        circuit_breaker::reset(&env, &pair, &admin);
+
+        Ok(())
+    }
+
+    /// Get the circuit breaker thresholds for a pair
+    pub fn get_circuit_breaker(env: Env, pair: Symbol) -> Option<CircuitBreakerConfig> {
+        env.storage().persistent().get(&DataKey::CircuitConfig(pair))
+    }
+
+    /// Get the circuit breaker state for a pair
+    pub fn get_circuit_state(env: Env, pair: Symbol) -> Option<PairCircuit> {
+        circuit_breaker::get_state(&env, &pair)
+    }
+
+    /// Get trading statistics for a single pair
+    pub fn get_pair_stats(env: Env, pair: Symbol) -> PairStats {
+        market_stats::get_pair_stats(&env, &pair)
+    }
+
+    /// Get OHLCV candles for a pair between two timestamps (inclusive).
+    /// A query may span at most `MAX_CANDLE_RANGE` buckets.
+    pub fn get_candles(
+        env: Env,
+        pair: Symbol,
+        interval: Interval,
+        from: u64,
+        to: u64,
+    ) -> Result<soroban_sdk::Vec<Candle>, TradeError> {
+        market_stats::get_candles(&env, &pair, interval, from, to)
+    }
+
+    /// Get a trader's position in a pair
+    pub fn get_position(env: Env, trader: Address, pair: Symbol) -> Position {
+        positions::get_position(&env, &trader, &pair)
+    }
+
+    /// Get a trader's totals across all pairs
+    pub fn get_trader_stats(env: Env, trader: Address) -> TraderStats {
+        positions::get_trader_stats(&env, &trader)
+    }
+
+    /// Pause the contract (admin only)
+    pub fn pause(env: Env, admin: Address) -> Result<(), TradeError> {
+        admin.require_auth();
+
+        // Verify admin role
+        Self::require_admin(&env, &admin)?;
+
+        let paused_key = symbol_short!("pause");
+        env.storage().persistent().set(&paused_key, &true);
+
+        Ok(())
+    }
+
+    /// Unpause the contract (admin only)
+    pub fn unpause(env: Env, admin: Address) -> Result<(), TradeError> {
+        admin.require_auth();
+
+        Self::require_admin(&env, &admin)?;
+
+        let paused_key = symbol_short!("pause");
+        env.storage().persistent().set(&paused_key, &false);
+
+        Ok(())
+    }
+
+    /// Propose an upgrade via governance
+    pub fn propose_upgrade(
+        env: Env,
+        admin: Address,
+        new_contract_hash: BytesN<32>,
+        description: Symbol,
+        approvers: soroban_sdk::Vec<Address>,
+        approval_threshold: u32,
+        timelock_delay: u64,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::propose_upgrade(
+            &env,
+            admin,
+            new_contract_hash,
+            env.current_contract_address(),
+            description,
+            approval_threshold,
+            approvers,
+            timelock_delay,
+        )
+    }
+
+    /// Approve an upgrade proposal
+    pub fn approve_upgrade(
+        env: Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        approver.require_auth();
+
+        GovernanceManager::approve_proposal(&env, proposal_id, approver)
+    }
+
+    /// Execute an approved upgrade proposal
+    pub fn execute_upgrade(
+        env: Env,
+        proposal_id: u64,
+        executor: Address,
+    ) -> Result<(), GovernanceError> {
+        executor.require_auth();
+
+        GovernanceManager::execute_proposal(&env, proposal_id, executor)
+    }
+
+    /// Install new code when called by an external governance contract
+    /// holding the Governor role, e.g. while executing its upgrade proposal
+    pub fn upgrade(env: Env, governor: Address, new_wasm_hash: BytesN<32>) -> Result<(), TradeError> {
+        governor.require_auth();
+        Self::require_role(&env, &governor, GovernanceRole::Governor)?;
+
+        GovernanceManager::install_current_wasm(&env, &new_wasm_hash);
+
+        Ok(())
+    }
+
+    /// Get the WASM hash installed by the last executed upgrade
+    pub fn get_wasm_hash(env: Env) -> Option<BytesN<32>> {
+        GovernanceManager::get_wasm_hash(&env)
+    }
+
+    /// Get upgrade proposal details
+    pub fn get_upgrade_proposal(env: Env, proposal_id: u64) -> Result<UpgradeProposal, GovernanceError> {
+        GovernanceManager::get_proposal(&env, proposal_id)
+    }
+
+    /// Reject an upgrade proposal
+    pub fn reject_upgrade(
+        env: Env,
+        proposal_id: u64,
+        rejector: Address,
+    ) -> Result<(), GovernanceError> {
+        rejector.require_auth();
+
+        GovernanceManager::reject_proposal(&env, proposal_id, rejector)
+    }
+
+    /// Cancel an upgrade proposal (admin only)
+    pub fn cancel_upgrade(
+        env: Env,
+        proposal_id: u64,
+        admin: Address,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::cancel_proposal(&env, proposal_id, admin)
+    }
+
+    /// Propose a list of contract calls to run through multisig and timelock
+    pub fn propose_actions(
+        env: Env,
+        admin: Address,
+        actions: soroban_sdk::Vec<ProposalAction>,
+        description: Symbol,
+        approvers: soroban_sdk::Vec<Address>,
+        approval_threshold: u32,
+        timelock_delay: u64,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::propose_actions(
+            &env,
+            admin,
+            actions,
+            description,
+            approval_threshold,
+            approvers,
+            timelock_delay,
+        )
+    }
+
+    /// Propose emergency actions: shorter timelock, higher approval threshold
+    pub fn propose_emergency_actions(
+        env: Env,
+        admin: Address,
+        actions: soroban_sdk::Vec<ProposalAction>,
+        description: Symbol,
+        approvers: soroban_sdk::Vec<Address>,
+        approval_threshold: u32,
+        timelock_delay: u64,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::propose_emergency_actions(
+            &env,
+            admin,
+            actions,
+            description,
+            approval_threshold,
+            approvers,
+            timelock_delay,
+        )
+    }
+
+    /// Approve an action proposal
+    pub fn approve_actions(
+        env: Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        approver.require_auth();
+
+        GovernanceManager::approve_actions(&env, proposal_id, approver)
+    }
+
+    /// Execute an approved action proposal, returning each call's result
+    pub fn execute_actions(
+        env: Env,
+        proposal_id: u64,
+        executor: Address,
+    ) -> Result<soroban_sdk::Vec<Val>, GovernanceError> {
+        executor.require_auth();
+
+        GovernanceManager::execute_actions(&env, proposal_id, executor)
+    }
+
+    /// Reject an action proposal
+    pub fn reject_actions(
+        env: Env,
+        proposal_id: u64,
+        rejector: Address,
+    ) -> Result<(), GovernanceError> {
+        rejector.require_auth();
+
+        GovernanceManager::reject_actions(&env, proposal_id, rejector)
+    }
+
+    /// Cancel an action proposal (admin only)
+    pub fn cancel_actions(
+        env: Env,
+        proposal_id: u64,
+        admin: Address,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::cancel_actions(&env, proposal_id, admin)
+    }
+
+    /// Withdraw an approval from an upgrade or action proposal
+    pub fn revoke_approval(
+        env: Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        approver.require_auth();
+
+        GovernanceManager::revoke_approval(&env, proposal_id, approver)
+    }
+
+    /// Veto an upgrade or action proposal during its timelock (guardian only)
+    pub fn veto_proposal(
+        env: Env,
+        proposal_id: u64,
+        guardian: Address,
+    ) -> Result<(), GovernanceError> {
+        guardian.require_auth();
+
+        GovernanceManager::veto_proposal(&env, proposal_id, guardian)
+    }
+
+    /// Page through upgrade proposals among the `limit` IDs after `after_id`,
+    /// optionally filtered by status
+    pub fn list_proposals(
+        env: Env,
+        status: Option<ProposalStatus>,
+        after_id: u64,
+        limit: u32,
+    ) -> soroban_sdk::Vec<UpgradeProposal> {
+        GovernanceManager::list_proposals(&env, status, after_id, limit)
+    }
+
+    /// Page through action proposals among the `limit` IDs after `after_id`,
+    /// optionally filtered by status
+    pub fn list_action_proposals(
+        env: Env,
+        status: Option<ProposalStatus>,
+        after_id: u64,
+        limit: u32,
+    ) -> soroban_sdk::Vec<Proposal> {
+        GovernanceManager::list_action_proposals(&env, status, after_id, limit)
+    }
+
+    /// Get action proposal details
+    pub fn get_action_proposal(env: Env, proposal_id: u64) -> Result<Proposal, GovernanceError> {
+        GovernanceManager::get_action_proposal(&env, proposal_id)
+    }
+
+    /// Set approval and execution windows for new proposals (admin only)
+    pub fn set_governance_config(
+        env: Env,
+        admin: Address,
+        config: GovernanceConfig,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::set_config(&env, admin, config)
+    }
+
+    /// Get approval and execution windows for new proposals
+    pub fn get_governance_config(env: Env) -> GovernanceConfig {
+        GovernanceManager::get_config(&env)
+    }
+
+    /// List proposals past their approval deadline or execution grace period,
+    /// scanning the `limit` proposal IDs after `after_id`
+    pub fn get_expirable_proposals(env: Env, after_id: u64, limit: u32) -> soroban_sdk::Vec<u64> {
+        GovernanceManager::get_expirable_proposals(&env, after_id, limit)
+    }
+
+    /// Number of proposal IDs handed out so far
+    pub fn get_proposal_count(env: Env) -> u64 {
+        GovernanceManager::proposal_count(&env)
+    }
+
+    /// Mark a stale proposal Expired
+    pub fn expire_proposal(env: Env, proposal_id: u64) -> Result<(), GovernanceError> {
+        GovernanceManager::expire_proposal(&env, proposal_id)
+    }
+
+    /// Schedule granting a role; it takes effect after the role-change timelock (admin only)
+    pub fn grant_role(
+        env: Env,
+        admin: Address,
+        role: GovernanceRole,
+        account: Address,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::grant_role(&env, admin, role, account)
+    }
+
+    /// Schedule revoking a role; it takes effect after the role-change timelock (admin only)
+    pub fn revoke_role(
+        env: Env,
+        admin: Address,
+        role: GovernanceRole,
+        account: Address,
+    ) -> Result<u64, GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::revoke_role(&env, admin, role, account)
+    }
+
+    /// Apply a scheduled role change once its timelock has passed
+    pub fn execute_role_change(
+        env: Env,
+        change_id: u64,
+        executor: Address,
+    ) -> Result<(), GovernanceError> {
+        executor.require_auth();
+
+        GovernanceManager::execute_role_change(&env, change_id, executor)
+    }
+
+    /// Cancel a scheduled role change (admin only)
+    pub fn cancel_role_change(
+        env: Env,
+        change_id: u64,
+        admin: Address,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::cancel_role_change(&env, change_id, admin)
+    }
+
+    /// Give up one of the caller's own roles immediately
+    pub fn renounce_role(
+        env: Env,
+        account: Address,
+        role: GovernanceRole,
+    ) -> Result<(), GovernanceError> {
+        account.require_auth();
+
+        GovernanceManager::renounce_role(&env, account, role)
+    }
+
+    /// Configure which roles holders of `role` also act as (admin only)
+    pub fn set_role_hierarchy(
+        env: Env,
+        admin: Address,
+        role: GovernanceRole,
+        implied: soroban_sdk::Vec<GovernanceRole>,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+
+        GovernanceManager::set_implied_roles(&env, admin, role, implied)
+    }
+
+    /// Get the roles implied by `role`
+    pub fn get_role_hierarchy(env: Env, role: GovernanceRole) -> soroban_sdk::Vec<GovernanceRole> {
+        GovernanceManager::get_implied_roles(&env, role)
+    }
+
+    /// Get a scheduled role change
+    pub fn get_role_change(env: Env, change_id: u64) -> Result<RoleChange, GovernanceError> {
+        GovernanceManager::get_role_change(&env, change_id)
+    }
+
+    /// Get every address holding a role
+    pub fn get_role_members(env: Env, role: GovernanceRole) -> soroban_sdk::Vec<Address> {
+        GovernanceManager::get_role_members(&env, role)
+    }
+
+    /// Whether an address holds a role
+    pub fn has_role(env: Env, account: Address, role: GovernanceRole) -> bool {
+        GovernanceManager::has_role(&env, &account, role)
+    }
+
+    /// Configure token-weighted voting (admin only)
+    pub fn set_voting_config(
+        env: Env,
+        admin: Address,
+        config: VotingConfig,
+    ) -> Result<(), GovernanceError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin).map_err(|_| GovernanceError::Unauthorized)?;
+
+        TokenVoting::set_config(&env, config)
+    }
+
+    /// Get the token-weighted voting configuration
+    pub fn get_voting_config(env: Env) -> Result<VotingConfig, GovernanceError> {
+        TokenVoting::get_config(&env)
+    }
+
+    /// Register as a voter so new proposals snapshot your balance
+    pub fn register_voter(env: Env, voter: Address) -> Result<(), GovernanceError> {
+        voter.require_auth();
+
+        TokenVoting::register_voter(&env, &voter)
+    }
+
+    /// Stop new proposals from snapshotting your balance
+    pub fn unregister_voter(env: Env, voter: Address) -> Result<(), GovernanceError> {
+        voter.require_auth();
+
+        TokenVoting::unregister_voter(&env, &voter)
+    }
+
+    /// Get the voters whose balances new proposals snapshot
+    pub fn get_voters(env: Env) -> soroban_sdk::Vec<Address> {
+        TokenVoting::get_voters(&env)
+    }
+
+    /// Create a token-weighted proposal
+    pub fn propose_vote(
+        env: Env,
+        proposer: Address,
+        actions: soroban_sdk::Vec<ProposalAction>,
+        description: Symbol,
+    ) -> Result<u64, GovernanceError> {
+        proposer.require_auth();
+
+        TokenVoting::propose(&env, proposer, actions, description)
+    }
+
+    /// Vote on a token-weighted proposal, returning the voter's weight
+    pub fn cast_vote(
+        env: Env,
+        voter: Address,
+        proposal_id: u64,
+        support: VoteType,
+    ) -> Result<i128, GovernanceError> {
+        voter.require_auth();
+
+        TokenVoting::cast_vote(&env, proposal_id, &voter, support)
+    }
+
+    /// Queue a succeeded proposal into its timelock
+    pub fn queue_vote(env: Env, proposal_id: u64) -> Result<u64, GovernanceError> {
+        TokenVoting::queue(&env, proposal_id)
+    }
+
+    /// Execute a queued proposal once its timelock has passed
+    pub fn execute_vote(
+        env: Env,
+        proposal_id: u64,
+    ) -> Result<soroban_sdk::Vec<Val>, GovernanceError> {
+        TokenVoting::execute(&env, proposal_id)
+    }
+
+    /// Cancel a token-weighted proposal (admin only)
+    pub fn cancel_vote(env: Env, admin: Address, proposal_id: u64) -> Result<(), GovernanceError> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin).map_err(|_| GovernanceError::Unauthorized)?;
+
+        TokenVoting::cancel(&env, proposal_id)
+    }
+
+    /// Get a token-weighted proposal
+    pub fn get_vote_proposal(env: Env, proposal_id: u64) -> Result<VotingProposal, GovernanceError> {
+        TokenVoting::get_proposal(&env, proposal_id)
+    }
+
+    /// Get the current state of a token-weighted proposal
+    pub fn get_vote_state(env: Env, proposal_id: u64) -> Result<VotingState, GovernanceError> {
+        TokenVoting::state(&env, proposal_id)
+    }
+
+    /// Get a voter's vote on a proposal
+    pub fn get_vote_receipt(env: Env, proposal_id: u64, voter: Address) -> Option<VoteReceipt> {
+        TokenVoting::get_receipt(&env, proposal_id, &voter)
+    }
+
+    fn require_admin(env: &Env, admin: &Address) -> Result<(), TradeError> {
+        Self::require_role(env, admin, GovernanceRole::Admin)
+    }
+
+    fn require_role(
+        env: &Env,
+        address: &Address,
+        required: GovernanceRole,
+    ) -> Result<(), TradeError> {
+        GovernanceManager::require_role(env, address, required)
+            .map_err(|_| TradeError::Unauthorized)
+    }
+
+    /// Check a trade's bounds and execute it with the trader paying the fee
+    fn submit_trade(env: &Env, trader: &Address, request: &TradeRequest) -> Result<u64, TradeError> {
+        Self::check_bounds(env, request.amount, request.price, request.is_buy, &request.bounds)?;
+
+        Self::execute_trade(
+            env,
+            trader,
+            trader,
+            &request.pair,
+            request.amount,
+            request.price,
+            request.is_buy,
+            &request.fee,
+        )
+    }
+
+    /// Record a trade and collect its fee from `fee_payer`.
+    /// Callers are responsible for authorizing the trader.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_trade(
+        env: &Env,
+        trader: &Address,
+        fee_payer: &Address,
+        pair: &Symbol,
+        amount: i128,
+        price: i128,
+        is_buy: bool,
+        fee: &TradeFee,
+    ) -> Result<u64, TradeError> {
+        Self::validate_trade(env, pair, amount, price)?;
+
+        // Restricted pairs require a valid attestation
+        compliance::require_attestation(env, pair, trader)?;
+
+        // Count the trade against the trader's rate and notional limits
+        let usage = limits::check_trade(env, trader, amount, price)?;
+
+        // Collect fee first, routing the referral share if the trader was referred
+        let referrer: Option<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Referrer(trader.clone()));
+
+        match referrer.clone() {
+            Some(referrer) => {
+                let share_bps: u32 = env
+                    .storage()
+                    .persistent()
+                    .get(&symbol_short!("ref_bps"))
+                    .unwrap_or(0);
+
+                let reward = FeeManager::collect_fee_with_referral(
+                    env,
+                    &fee.token,
+                    fee_payer,
+                    &fee.recipient,
+                    &referrer,
+                    fee.amount,
+                    share_bps,
+                )?;
+
+                if reward > 0 {
+                    let rewards_key = DataKey::ReferralRewards(referrer, fee.token.clone());
+                    let earned: i128 = env.storage().persistent().get(&rewards_key).unwrap_or(0);
+                    env.storage().persistent().set(&rewards_key, &(earned + reward));
+                }
+            }
+            None => {
+                FeeManager::charge_fee(env, &fee.token, fee_payer, &fee.recipient, fee.amount)?;
+            }
+        }
+
+        // Nothing below can fail, so a rejected trade leaves no state behind
+        limits::save_usage(env, trader, &usage);
+
+        // Create trade record
+        let stats_key = symbol_short!("stats");
+        let mut stats: TradeStats = env
+            .storage()
+            .persistent()
+            .get(&stats_key)
+            .unwrap_or(TradeStats {
+                total_trades: 0,
+                total_volume: 0,
+                last_trade_id: 0,
+            });
+
+        let trade_id = stats.last_trade_id + 1;
+
+        // Halt the pair for later trades if this one moved the price too far
+        circuit_breaker::record_price(env, pair, price);
+
+        if let Some(referrer) = referrer {
+            let ref_stats_key = DataKey::ReferralStats(referrer);
+            let mut ref_stats = Self::load_referral_stats(env, &ref_stats_key);
+            ref_stats.referred_trades += 1;
+            ref_stats.referred_volume += amount;
+            env.storage().persistent().set(&ref_stats_key, &ref_stats);
+        }
+
+        // Update the trader's position and realized PnL
+        let pnl = positions::record_trade(env, trader, pair, amount, price, is_buy);
+
+        // Update scores in running competitions
+        competitions::record_trade(env, trader, pair, amount, pnl);
+
+        // Update per-pair totals and OHLCV candles
+        market_stats::record_trade(env, pair, trade_id, amount, price);
+
+        let trade = Trade {
+            id: trade_id,
+            trader: trader.clone(),
+            pair: pair.clone(),
+            amount,
+            price,
+            timestamp: env.ledger().timestamp(),
+            is_buy,
+        };
+
+        // Update stats
+        stats.total_trades += 1;
+        stats.total_volume += amount;
+        stats.last_trade_id = trade_id;
+
+        // Update persistent storage
+        env.storage()
+            .persistent()
+            .set(&DataKey::Trade(trade_id), &trade);
+        env.storage().persistent().set(&stats_key, &stats);
+
+        Ok(trade_id)
+    }
+
+    /// Checks shared by real and simulated trades
+    fn validate_trade(env: &Env, pair: &Symbol, amount: i128, price: i128) -> Result<(), TradeError> {
+        if amount <= 0 || price <= 0 {
+            return Err(TradeError::InvalidAmount);
+        }
+
+        // Verify not paused
+        let paused_key = symbol_short!("pause");
+        let is_paused: bool = env
+            .storage()
+            .persistent()
+            .get(&paused_key)
+            .unwrap_or(false);
+
+        if is_paused {
+            return Err(TradeError::ContractPaused);
+        }
+
+        // Reject trades on a pair halted by its circuit breaker
+        circuit_breaker::require_not_halted(env, pair)?;
+
+        // Reject prices outside the pair's oracle band
+        Self::check_oracle_price(env, pair, price)?;
+
+        Ok(())
+    }
+
+    fn check_bounds(
+        env: &Env,
+        amount: i128,
+        price: i128,
+        is_buy: bool,
+        bounds: &TradeBounds,
+    ) -> Result<(), TradeError> {
+        if env.ledger().timestamp() > bounds.deadline {
+            return Err(TradeError::DeadlineExpired);
+        }
+
+        let quote = amount.checked_mul(price).ok_or(TradeError::InvalidAmount)?;
+        let (amount_in, amount_out) = if is_buy { (quote, amount) } else { (amount, quote) };
+
+        if amount_out < bounds.min_out {
+            return Err(TradeError::InsufficientOutput);
+        }
+
+        if bounds.max_in > 0 && amount_in > bounds.max_in {
+            return Err(TradeError::ExcessiveInput);
+        }
+
+        Ok(())
+    }
+
+    fn check_oracle_price(env: &Env, pair: &Symbol, price: i128) -> Result<(), TradeError> {
+        let config: Option<PairOracle> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PairOracle(pair.clone()));
+
+        // Pairs without an oracle accept any price
+        let config = match config {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+
+        let reference = OracleManager::lastprice(env, &config.oracle, &config.asset)?;
+        OracleManager::check_price(
+            env,
+            price,
+            &reference,
+            config.max_deviation_bps,
+            config.max_age,
+        )?;
+
+        Ok(())
+    }
+
+    fn load_referral_stats(env: &Env, key: &DataKey) -> ReferralStats {
+        env.storage()
+            .persistent()
+            .get(key)
+            .unwrap_or(ReferralStats {
+                referred_traders: 0,
+                referred_trades: 0,
+                referred_volume: 0,
+            })
     }
 }
 
@@ -0,0 +1,138 @@
+use soroban_sdk::{contracttype, symbol_short, Address, Env};
+
+use crate::{DataKey, TradeError};
+
+const DAY_SECONDS: u64 = 86_400;
+
+/// Per-trader throttles. A zero field disables that limit.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraderLimits {
+    pub max_trades_per_window: u32, // Trades allowed per window of ledgers
+    pub window_ledgers: u32,        // Window length in ledgers
+    pub max_daily_notional: i128,   // Sum of amount * price allowed per UTC day
+    pub max_open_orders: u32,       // Open conditional orders plus active schedules
+}
+
+/// Usage counted against a trader's limits
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraderUsage {
+    pub window: u32,        // Index of the current ledger window
+    pub window_trades: u32,
+    pub day: u64,           // Index of the current day
+    pub day_notional: i128,
+    pub open_orders: u32,
+}
+
+pub fn validate_limits(limits: &TraderLimits) -> Result<(), TradeError> {
+    if limits.max_daily_notional < 0
+        || (limits.max_trades_per_window > 0 && limits.window_ledgers == 0)
+    {
+        return Err(TradeError::InvalidLimitConfig);
+    }
+
+    Ok(())
+}
+
+fn unlimited() -> TraderLimits {
+    TraderLimits {
+        max_trades_per_window: 0,
+        window_ledgers: 0,
+        max_daily_notional: 0,
+        max_open_orders: 0,
+    }
+}
+
+/// Limits applied to a trader: their override if set, otherwise the default
+pub fn get_limits(env: &Env, trader: &Address) -> TraderLimits {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TraderLimits(trader.clone()))
+        .or_else(|| env.storage().persistent().get(&symbol_short!("limits")))
+        .unwrap_or_else(unlimited)
+}
+
+pub fn get_usage(env: &Env, trader: &Address) -> TraderUsage {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TraderUsage(trader.clone()))
+        .unwrap_or(TraderUsage {
+            window: 0,
+            window_trades: 0,
+            day: 0,
+            day_notional: 0,
+            open_orders: 0,
+        })
+}
+
+pub fn save_usage(env: &Env, trader: &Address, usage: &TraderUsage) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::TraderUsage(trader.clone()), usage);
+}
+
+/// Check a trade against the trader's rate and notional limits and return
+/// the usage to store once the trade goes through
+pub fn check_trade(
+    env: &Env,
+    trader: &Address,
+    amount: i128,
+    price: i128,
+) -> Result<TraderUsage, TradeError> {
+    let limits = get_limits(env, trader);
+    let mut usage = get_usage(env, trader);
+
+    // Start fresh counters when a new window or day begins
+    if let Some(window) = env.ledger().sequence().checked_div(limits.window_ledgers) {
+        if window != usage.window {
+            usage.window = window;
+            usage.window_trades = 0;
+        }
+    }
+
+    let day = env.ledger().timestamp() / DAY_SECONDS;
+    if day != usage.day {
+        usage.day = day;
+        usage.day_notional = 0;
+    }
+
+    if limits.max_trades_per_window > 0 && usage.window_trades >= limits.max_trades_per_window {
+        return Err(TradeError::TradeRateLimited);
+    }
+
+    let notional = amount
+        .checked_mul(price)
+        .and_then(|notional| notional.checked_add(usage.day_notional))
+        .ok_or(TradeError::DailyNotionalExceeded)?;
+    if limits.max_daily_notional > 0 && notional > limits.max_daily_notional {
+        return Err(TradeError::DailyNotionalExceeded);
+    }
+
+    usage.window_trades += 1;
+    usage.day_notional = notional;
+
+    Ok(usage)
+}
+
+/// Count a newly placed order or schedule against the open order limit
+pub fn open_order(env: &Env, trader: &Address) -> Result<(), TradeError> {
+    let limits = get_limits(env, trader);
+    let mut usage = get_usage(env, trader);
+
+    if limits.max_open_orders > 0 && usage.open_orders >= limits.max_open_orders {
+        return Err(TradeError::TooManyOpenOrders);
+    }
+
+    usage.open_orders += 1;
+    save_usage(env, trader, &usage);
+
+    Ok(())
+}
+
+/// Release an order or schedule that is no longer open
+pub fn close_order(env: &Env, trader: &Address) {
+    let mut usage = get_usage(env, trader);
+    usage.open_orders = usage.open_orders.saturating_sub(1);
+    save_usage(env, trader, &usage);
+}
@@ -0,0 +1,130 @@
+use soroban_sdk::{contracttype, Env, Symbol, Vec};
+
+use crate::{DataKey, TradeError};
+
+/// Largest number of buckets a single candle query may span
+pub const MAX_CANDLE_RANGE: u64 = 200;
+
+/// Candle bucket length
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum Interval {
+    Hour = 0,
+    Day = 1,
+}
+
+impl Interval {
+    pub fn seconds(&self) -> u64 {
+        match self {
+            Interval::Hour => 3_600,
+            Interval::Day => 86_400,
+        }
+    }
+}
+
+/// Trading statistics for a single pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairStats {
+    pub total_trades: u64,
+    pub total_volume: i128,
+    pub last_price: i128,
+    pub last_trade_id: u64,
+}
+
+/// OHLCV candle for one bucket of a pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Candle {
+    pub start: u64, // Bucket start timestamp
+    pub open: i128,
+    pub high: i128,
+    pub low: i128,
+    pub close: i128,
+    pub volume: i128,
+    pub trade_count: u32,
+}
+
+pub fn get_pair_stats(env: &Env, pair: &Symbol) -> PairStats {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PairStats(pair.clone()))
+        .unwrap_or(PairStats {
+            total_trades: 0,
+            total_volume: 0,
+            last_price: 0,
+            last_trade_id: 0,
+        })
+}
+
+/// Update pair totals and the hourly and daily candles after a trade
+pub fn record_trade(env: &Env, pair: &Symbol, trade_id: u64, amount: i128, price: i128) {
+    let mut stats = get_pair_stats(env, pair);
+    stats.total_trades += 1;
+    stats.total_volume += amount;
+    stats.last_price = price;
+    stats.last_trade_id = trade_id;
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::PairStats(pair.clone()), &stats);
+
+    let now = env.ledger().timestamp();
+    for interval in [Interval::Hour, Interval::Day] {
+        let bucket = now / interval.seconds();
+        let key = DataKey::Candle(pair.clone(), interval, bucket);
+
+        let candle = match env.storage().persistent().get::<DataKey, Candle>(&key) {
+            Some(mut candle) => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.volume += amount;
+                candle.trade_count += 1;
+                candle
+            }
+            None => Candle {
+                start: bucket * interval.seconds(),
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: amount,
+                trade_count: 1,
+            },
+        };
+
+        env.storage().persistent().set(&key, &candle);
+    }
+}
+
+/// Candles for the buckets covering `[from, to]`, oldest first. Buckets
+/// without trades are skipped.
+pub fn get_candles(
+    env: &Env,
+    pair: &Symbol,
+    interval: Interval,
+    from: u64,
+    to: u64,
+) -> Result<Vec<Candle>, TradeError> {
+    let first = from / interval.seconds();
+    let last = to / interval.seconds();
+
+    if from > to || last - first >= MAX_CANDLE_RANGE {
+        return Err(TradeError::InvalidBucketRange);
+    }
+
+    let mut candles = Vec::new(env);
+    for bucket in first..=last {
+        if let Some(candle) = env
+            .storage()
+            .persistent()
+            .get::<DataKey, Candle>(&DataKey::Candle(pair.clone(), interval, bucket))
+        {
+            candles.push_back(candle);
+        }
+    }
+
+    Ok(candles)
+}
@@ -0,0 +1,101 @@
+use soroban_sdk::{contracterror, symbol_short, Env, Vec};
+use shared::governance::{voting::TokenVoting, GovernanceManager};
+
+use crate::{DataKey, Trade, CONTRACT_VERSION};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum MigrationError {
+    Unauthorized = 6001,
+    DowngradeNotAllowed = 6002,
+    InvalidMigration = 6003,
+}
+
+/// A storage migration from `from_version` to `from_version + 1`
+struct Migration {
+    from_version: u32,
+    run: fn(&Env),
+}
+
+/// Registered migration steps, one per version bump
+const MIGRATIONS: [Migration; 6] = [
+    Migration {
+        from_version: 1,
+        run: split_trades_into_keys,
+    },
+    Migration {
+        from_version: 2,
+        run: GovernanceManager::migrate_legacy_roles,
+    },
+    Migration {
+        from_version: 3,
+        run: GovernanceManager::migrate_legacy_proposals,
+    },
+    Migration {
+        from_version: 4,
+        run: GovernanceManager::migrate_proposal_maps,
+    },
+    Migration {
+        from_version: 5,
+        run: GovernanceManager::migrate_proposal_indexes,
+    },
+    Migration {
+        from_version: 6,
+        run: TokenVoting::migrate_proposal_config,
+    },
+];
+
+pub fn get_version(env: &Env) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&symbol_short!("ver"))
+        .unwrap_or(0)
+}
+
+/// Bring storage written by `from_version` up to `CONTRACT_VERSION`,
+/// running every registered step in between.
+pub fn migrate(env: &Env, from_version: u32) -> Result<u32, MigrationError> {
+    let storage_version = get_version(env);
+    if from_version > CONTRACT_VERSION || storage_version > CONTRACT_VERSION {
+        return Err(MigrationError::DowngradeNotAllowed);
+    }
+
+    // Storage must actually be at the version being migrated from
+    if from_version == CONTRACT_VERSION || storage_version != from_version {
+        return Err(MigrationError::InvalidMigration);
+    }
+
+    for version in from_version..CONTRACT_VERSION {
+        if let Some(step) = MIGRATIONS.iter().find(|m| m.from_version == version) {
+            (step.run)(env);
+        }
+    }
+
+    env.storage()
+        .persistent()
+        .set(&symbol_short!("ver"), &CONTRACT_VERSION);
+
+    env.events()
+        .publish((symbol_short!("migrated"),), (from_version, CONTRACT_VERSION));
+
+    Ok(CONTRACT_VERSION)
+}
+
+/// v1 -> v2: move the monolithic `"trades"` Vec into one key per trade
+fn split_trades_into_keys(env: &Env) {
+    let trades_key = symbol_short!("trades");
+    let trades: Vec<Trade> = env
+        .storage()
+        .persistent()
+        .get(&trades_key)
+        .unwrap_or_else(|| Vec::new(env));
+
+    for trade in trades.iter() {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Trade(trade.id), &trade);
+    }
+
+    env.storage().persistent().remove(&trades_key);
+}
@@ -0,0 +1,287 @@
+use soroban_sdk::{contracttype, symbol_short, token, Address, Env, Symbol};
+use shared::oracle::OracleManager;
+
+use crate::{limits, market_stats, DataKey, PairOracle, TradeError, TradeFee};
+
+/// Conditional order type
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum OrderKind {
+    StopMarket = 0, // Market fill once price moves against the order side
+    StopLimit = 1,  // Fill at `limit_price` once triggered, if still reachable
+    TakeProfit = 2, // Market fill once price moves in favour of the order side
+}
+
+/// Price an order's trigger is evaluated against.
+///
+/// Any trade moves the last trade price, so `LastTrade` triggers only fire on
+/// pairs with an oracle, whose deviation band bounds how far a trade can push it.
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum TriggerSource {
+    LastTrade = 0, // Last trade price of the pair (requires `set_pair_oracle`)
+    Oracle = 1,    // The pair's oracle price (requires `set_pair_oracle`)
+}
+
+/// Conditional order lifecycle
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum OrderStatus {
+    Open = 0,
+    Executed = 1,
+    Cancelled = 2,
+    Expired = 3,
+}
+
+/// Parameters supplied when placing a conditional order
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrderRequest {
+    pub pair: Symbol,
+    pub amount: i128,
+    pub is_buy: bool,
+    pub kind: OrderKind,
+    pub trigger_price: i128,
+    pub limit_price: i128,         // Only used by StopLimit
+    pub trigger_source: TriggerSource,
+    pub expiry: u64,               // Timestamp after which the order can no longer fill
+    pub fee: TradeFee,             // Trade fee, escrowed at placement
+    pub bounty: i128,              // Paid to the keeper in the fee token, escrowed at placement
+}
+
+/// Resting conditional order
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConditionalOrder {
+    pub id: u64,
+    pub owner: Address,
+    pub request: OrderRequest,
+    pub status: OrderStatus,
+    pub created_at: u64,
+    pub trade_id: u64,             // Trade produced on execution (0 until executed)
+}
+
+/// Order trigger event for off-chain indexing
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct OrderTriggeredEvent {
+    pub order_id: u64,
+    pub keeper: Address,
+    pub trigger_price: i128,
+    pub reference_price: i128,
+    pub trade_id: u64,
+}
+
+pub fn get_order(env: &Env, id: u64) -> Option<ConditionalOrder> {
+    env.storage().persistent().get(&DataKey::Order(id))
+}
+
+fn save_order(env: &Env, order: &ConditionalOrder) {
+    env.storage().persistent().set(&DataKey::Order(order.id), order);
+}
+
+/// Amount held in escrow for an order
+fn escrow_amount(request: &OrderRequest) -> Result<i128, TradeError> {
+    request
+        .fee
+        .amount
+        .checked_add(request.bounty)
+        .ok_or(TradeError::InvalidOrder)
+}
+
+/// Validate and store a new order, escrowing its fee and bounty
+pub fn place(env: &Env, owner: &Address, request: OrderRequest) -> Result<u64, TradeError> {
+    if request.amount <= 0
+        || request.trigger_price <= 0
+        || request.fee.amount < 0
+        || request.bounty < 0
+        || request.expiry <= env.ledger().timestamp()
+        || (request.kind == OrderKind::StopLimit && request.limit_price <= 0)
+    {
+        return Err(TradeError::InvalidOrder);
+    }
+
+    let escrow = escrow_amount(&request)?;
+    limits::open_order(env, owner)?;
+
+    if escrow > 0 {
+        token::Client::new(env, &request.fee.token).transfer(
+            owner,
+            &env.current_contract_address(),
+            &escrow,
+        );
+    }
+
+    let counter_key = symbol_short!("ord_cnt");
+    let id: u64 = env.storage().persistent().get(&counter_key).unwrap_or(0u64) + 1;
+
+    let order = ConditionalOrder {
+        id,
+        owner: owner.clone(),
+        request,
+        status: OrderStatus::Open,
+        created_at: env.ledger().timestamp(),
+        trade_id: 0,
+    };
+
+    save_order(env, &order);
+    env.storage().persistent().set(&counter_key, &id);
+
+    env.events()
+        .publish((symbol_short!("ord_new"), id), order.owner.clone());
+
+    Ok(id)
+}
+
+/// Current price of a pair from the given source
+pub fn reference_price(env: &Env, pair: &Symbol, source: TriggerSource) -> Result<i128, TradeError> {
+    match source {
+        TriggerSource::LastTrade => {
+            let price = market_stats::get_pair_stats(env, pair).last_price;
+            if price <= 0 {
+                return Err(TradeError::NoReferencePrice);
+            }
+            Ok(price)
+        }
+        TriggerSource::Oracle => {
+            let config: PairOracle = env
+                .storage()
+                .persistent()
+                .get(&DataKey::PairOracle(pair.clone()))
+                .ok_or(TradeError::NoReferencePrice)?;
+
+            let data = OracleManager::lastprice(env, &config.oracle, &config.asset)?;
+            if env.ledger().timestamp().saturating_sub(data.timestamp) > config.max_age {
+                return Err(TradeError::StalePrice);
+            }
+            if data.price <= 0 {
+                return Err(TradeError::NoReferencePrice);
+            }
+            Ok(data.price)
+        }
+    }
+}
+
+/// Current price an order's trigger is checked against
+pub fn trigger_reference(env: &Env, request: &OrderRequest) -> Result<i128, TradeError> {
+    if request.trigger_source == TriggerSource::LastTrade
+        && !env
+            .storage()
+            .persistent()
+            .has(&DataKey::PairOracle(request.pair.clone()))
+    {
+        return Err(TradeError::NoReferencePrice);
+    }
+
+    reference_price(env, &request.pair, request.trigger_source)
+}
+
+/// Whether `price` crosses the order's trigger.
+///
+/// Stops trigger when price moves against the order side (sell at or below,
+/// buy at or above); take-profits trigger when it moves in favour.
+pub fn is_triggered(request: &OrderRequest, price: i128) -> bool {
+    let sell_side_hit = price <= request.trigger_price;
+    let buy_side_hit = price >= request.trigger_price;
+
+    match (request.kind, request.is_buy) {
+        (OrderKind::StopMarket | OrderKind::StopLimit, false) => sell_side_hit,
+        (OrderKind::StopMarket | OrderKind::StopLimit, true) => buy_side_hit,
+        (OrderKind::TakeProfit, false) => buy_side_hit,
+        (OrderKind::TakeProfit, true) => sell_side_hit,
+    }
+}
+
+/// Price a triggered order fills at
+pub fn fill_price(request: &OrderRequest, reference: i128) -> Result<i128, TradeError> {
+    if request.kind != OrderKind::StopLimit {
+        return Ok(reference);
+    }
+
+    // A stop-limit only fills while the market is at or better than its limit
+    let reachable = if request.is_buy {
+        reference <= request.limit_price
+    } else {
+        reference >= request.limit_price
+    };
+
+    if !reachable {
+        return Err(TradeError::LimitPriceNotMet);
+    }
+
+    Ok(request.limit_price)
+}
+
+/// Mark an order executed and pay the keeper's bounty from escrow
+pub fn complete(
+    env: &Env,
+    order: &mut ConditionalOrder,
+    keeper: &Address,
+    reference: i128,
+    trade_id: u64,
+) {
+    order.status = OrderStatus::Executed;
+    order.trade_id = trade_id;
+    save_order(env, order);
+    limits::close_order(env, &order.owner);
+
+    if order.request.bounty > 0 {
+        token::Client::new(env, &order.request.fee.token).transfer(
+            &env.current_contract_address(),
+            keeper,
+            &order.request.bounty,
+        );
+    }
+
+    env.events().publish(
+        (symbol_short!("ord_trig"), order.id),
+        OrderTriggeredEvent {
+            order_id: order.id,
+            keeper: keeper.clone(),
+            trigger_price: order.request.trigger_price,
+            reference_price: reference,
+            trade_id,
+        },
+    );
+}
+
+/// Cancel an owner's open order
+pub fn cancel(env: &Env, owner: &Address, order_id: u64) -> Result<(), TradeError> {
+    let mut order = get_order(env, order_id).ok_or(TradeError::OrderNotFound)?;
+    if order.owner != *owner {
+        return Err(TradeError::Unauthorized);
+    }
+    if order.status != OrderStatus::Open {
+        return Err(TradeError::OrderNotOpen);
+    }
+
+    close(env, &mut order, OrderStatus::Cancelled)
+}
+
+/// Close an open order and refund its escrow to the owner
+pub fn close(env: &Env, order: &mut ConditionalOrder, status: OrderStatus) -> Result<(), TradeError> {
+    let escrow = escrow_amount(&order.request)?;
+
+    order.status = status;
+    save_order(env, order);
+    limits::close_order(env, &order.owner);
+
+    if escrow > 0 {
+        token::Client::new(env, &order.request.fee.token).transfer(
+            &env.current_contract_address(),
+            &order.owner,
+            &escrow,
+        );
+    }
+
+    let topic = match status {
+        OrderStatus::Expired => symbol_short!("ord_exp"),
+        _ => symbol_short!("ord_cncl"),
+    };
+    env.events().publish((topic, order.id), order.owner.clone());
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+use crate::DataKey;
+
+/// Net position of a trader in a pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Position {
+    pub base_qty: i128,        // Net base quantity, negative when short
+    pub avg_entry_price: i128, // Average price of the open quantity (0 when flat)
+    pub realized_pnl: i128,    // Realized PnL in quote units (amount * price)
+    pub updated_at: u64,
+}
+
+/// Per-trader totals across all pairs
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraderStats {
+    pub total_trades: u64,
+    pub total_volume: i128,
+    pub realized_pnl: i128,
+}
+
+pub fn get_position(env: &Env, trader: &Address, pair: &Symbol) -> Position {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Position(trader.clone(), pair.clone()))
+        .unwrap_or(Position {
+            base_qty: 0,
+            avg_entry_price: 0,
+            realized_pnl: 0,
+            updated_at: 0,
+        })
+}
+
+pub fn get_trader_stats(env: &Env, trader: &Address) -> TraderStats {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TraderStats(trader.clone()))
+        .unwrap_or(TraderStats {
+            total_trades: 0,
+            total_volume: 0,
+            realized_pnl: 0,
+        })
+}
+
+/// Apply a fill to a position and return the PnL it realized.
+///
+/// Fills in the direction of the position average into the entry price;
+/// opposite fills close quantity at `price` and realize PnL against the
+/// entry price. A fill that crosses zero opens the remainder at `price`.
+pub fn apply_fill(position: &mut Position, amount: i128, price: i128, is_buy: bool) -> i128 {
+    let signed = if is_buy { amount } else { -amount };
+    let held = position.base_qty;
+
+    if held == 0 || (held > 0) == is_buy {
+        let open = held.abs();
+        position.avg_entry_price =
+            (open * position.avg_entry_price + amount * price) / (open + amount);
+        position.base_qty = held + signed;
+        return 0;
+    }
+
+    let closed = amount.min(held.abs());
+    let pnl = if held > 0 {
+        closed * (price - position.avg_entry_price)
+    } else {
+        closed * (position.avg_entry_price - price)
+    };
+
+    position.realized_pnl += pnl;
+    position.base_qty = held + signed;
+
+    if position.base_qty == 0 {
+        position.avg_entry_price = 0;
+    } else if (position.base_qty > 0) != (held > 0) {
+        position.avg_entry_price = price;
+    }
+
+    pnl
+}
+
+/// Update the trader's position in a pair and their totals after a trade.
+/// Returns the PnL realized by the trade.
+pub fn record_trade(
+    env: &Env,
+    trader: &Address,
+    pair: &Symbol,
+    amount: i128,
+    price: i128,
+    is_buy: bool,
+) -> i128 {
+    let mut position = get_position(env, trader, pair);
+    let pnl = apply_fill(&mut position, amount, price, is_buy);
+    position.updated_at = env.ledger().timestamp();
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::Position(trader.clone(), pair.clone()), &position);
+
+    let mut stats = get_trader_stats(env, trader);
+    stats.total_trades += 1;
+    stats.total_volume += amount;
+    stats.realized_pnl += pnl;
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::TraderStats(trader.clone()), &stats);
+
+    pnl
+}
@@ -0,0 +1,227 @@
+use soroban_sdk::{contracttype, symbol_short, token, Address, Env, Symbol};
+
+use crate::orders::{self, TriggerSource};
+use crate::{limits, DataKey, TradeError, TradeFee};
+
+/// Recurring schedule lifecycle
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum ScheduleStatus {
+    Active = 0,
+    Completed = 1,
+    Cancelled = 2,
+}
+
+/// Parameters supplied when creating a TWAP or DCA schedule
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScheduleRequest {
+    pub pair: Symbol,
+    pub is_buy: bool,
+    pub total_amount: i128,        // Amount traded over the whole schedule
+    pub slice_amount: i128,        // Amount traded per execution (the last slice may be smaller)
+    pub interval: u64,             // Seconds between slices
+    pub start_time: u64,           // First slice is due at this timestamp
+    pub end_time: u64,             // No slice executes after this timestamp
+    pub price_source: TriggerSource, // Price each slice fills at
+    pub limit_price: i128,         // Worst acceptable fill price, 0 for none
+    pub fee: TradeFee,             // Trade fee per slice
+    pub bounty: i128,              // Paid to the keeper per slice in the fee token
+}
+
+/// Recurring order schedule with its escrow
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schedule {
+    pub id: u64,
+    pub owner: Address,
+    pub request: ScheduleRequest,
+    pub status: ScheduleStatus,
+    pub executed_amount: i128,
+    pub slices_executed: u32,
+    pub next_execution: u64,
+    pub escrow: i128,              // Fee token still held for unexecuted slices
+    pub created_at: u64,
+}
+
+pub fn get_schedule(env: &Env, id: u64) -> Option<Schedule> {
+    env.storage().persistent().get(&DataKey::Schedule(id))
+}
+
+fn save_schedule(env: &Env, schedule: &Schedule) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::Schedule(schedule.id), schedule);
+}
+
+/// Number of slices needed to trade the full amount
+fn slice_count(request: &ScheduleRequest) -> i128 {
+    (request.total_amount + request.slice_amount - 1) / request.slice_amount
+}
+
+/// Validate and store a new schedule, escrowing the fee and bounty for every slice
+pub fn create(env: &Env, owner: &Address, request: ScheduleRequest) -> Result<u64, TradeError> {
+    let now = env.ledger().timestamp();
+
+    if request.total_amount <= 0
+        || request.slice_amount <= 0
+        || request.slice_amount > request.total_amount
+        || request.interval == 0
+        || request.limit_price < 0
+        || request.fee.amount < 0
+        || request.bounty < 0
+        || request.start_time < now
+        || request.end_time <= request.start_time
+    {
+        return Err(TradeError::InvalidSchedule);
+    }
+
+    // Every slice must fall due before the schedule ends
+    let slices = slice_count(&request);
+    let last_slice_at = (slices as u64 - 1)
+        .checked_mul(request.interval)
+        .and_then(|offset| request.start_time.checked_add(offset))
+        .ok_or(TradeError::InvalidSchedule)?;
+    if last_slice_at > request.end_time {
+        return Err(TradeError::InvalidSchedule);
+    }
+
+    limits::open_order(env, owner)?;
+
+    let escrow = (request.fee.amount + request.bounty)
+        .checked_mul(slices)
+        .ok_or(TradeError::InvalidSchedule)?;
+    if escrow > 0 {
+        token::Client::new(env, &request.fee.token).transfer(
+            owner,
+            &env.current_contract_address(),
+            &escrow,
+        );
+    }
+
+    let counter_key = symbol_short!("sch_cnt");
+    let id: u64 = env.storage().persistent().get(&counter_key).unwrap_or(0u64) + 1;
+
+    let schedule = Schedule {
+        id,
+        owner: owner.clone(),
+        next_execution: request.start_time,
+        request,
+        status: ScheduleStatus::Active,
+        executed_amount: 0,
+        slices_executed: 0,
+        escrow,
+        created_at: now,
+    };
+
+    save_schedule(env, &schedule);
+    env.storage().persistent().set(&counter_key, &id);
+
+    env.events()
+        .publish((symbol_short!("sch_new"), id), schedule.owner.clone());
+
+    Ok(id)
+}
+
+/// Amount and fill price of the schedule's next slice, if it is due
+pub fn next_slice(env: &Env, schedule: &Schedule) -> Result<(i128, i128), TradeError> {
+    if schedule.status != ScheduleStatus::Active {
+        return Err(TradeError::ScheduleNotActive);
+    }
+
+    let now = env.ledger().timestamp();
+    if now > schedule.request.end_time {
+        return Err(TradeError::ScheduleEnded);
+    }
+    if now < schedule.next_execution {
+        return Err(TradeError::SliceNotDue);
+    }
+
+    let request = &schedule.request;
+    let price = orders::reference_price(env, &request.pair, request.price_source)?;
+
+    if request.limit_price > 0 {
+        let acceptable = if request.is_buy {
+            price <= request.limit_price
+        } else {
+            price >= request.limit_price
+        };
+        if !acceptable {
+            return Err(TradeError::LimitPriceNotMet);
+        }
+    }
+
+    let remaining = request.total_amount - schedule.executed_amount;
+    Ok((remaining.min(request.slice_amount), price))
+}
+
+/// Record an executed slice and pay the keeper's bounty from escrow.
+///
+/// Slices are due on a fixed grid from `start_time`, so a late keeper can
+/// catch up on missed slices one call at a time.
+pub fn complete_slice(
+    env: &Env,
+    schedule: &mut Schedule,
+    keeper: &Address,
+    amount: i128,
+    trade_id: u64,
+) {
+    let request = schedule.request.clone();
+
+    schedule.executed_amount += amount;
+    schedule.slices_executed += 1;
+    schedule.next_execution += request.interval;
+    schedule.escrow -= request.fee.amount + request.bounty;
+
+    if schedule.executed_amount >= request.total_amount {
+        schedule.status = ScheduleStatus::Completed;
+        limits::close_order(env, &schedule.owner);
+    }
+
+    save_schedule(env, schedule);
+
+    if request.bounty > 0 {
+        token::Client::new(env, &request.fee.token).transfer(
+            &env.current_contract_address(),
+            keeper,
+            &request.bounty,
+        );
+    }
+
+    env.events().publish(
+        (symbol_short!("sch_exec"), schedule.id),
+        (keeper.clone(), trade_id, amount),
+    );
+
+    if schedule.status == ScheduleStatus::Completed {
+        env.events()
+            .publish((symbol_short!("sch_done"), schedule.id), schedule.executed_amount);
+    }
+}
+
+/// Cancel an active schedule and refund the escrow for unexecuted slices
+pub fn cancel(env: &Env, schedule: &mut Schedule) -> Result<i128, TradeError> {
+    if schedule.status != ScheduleStatus::Active {
+        return Err(TradeError::ScheduleNotActive);
+    }
+
+    let refund = schedule.escrow;
+    schedule.status = ScheduleStatus::Cancelled;
+    schedule.escrow = 0;
+    save_schedule(env, schedule);
+    limits::close_order(env, &schedule.owner);
+
+    if refund > 0 {
+        token::Client::new(env, &schedule.request.fee.token).transfer(
+            &env.current_contract_address(),
+            &schedule.owner,
+            &refund,
+        );
+    }
+
+    env.events()
+        .publish((symbol_short!("sch_cncl"), schedule.id), refund);
+
+    Ok(refund)
+}
@@ -0,0 +1,163 @@
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol, Vec};
+
+use crate::positions::{self, Position};
+use crate::{DataKey, Trade, TradeError, TradeStats};
+
+/// Virtual quote balance sandboxes start with until an admin sets one
+pub const DEFAULT_STARTING_BALANCE: i128 = 10_000;
+
+/// Paper-trading account for an academy learner.
+/// Balances are virtual and never backed by tokens.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SimAccount {
+    pub quote_balance: i128,  // Virtual quote currency available to buy with
+    pub pairs: Vec<Symbol>,   // Pairs with a simulated position, for resets
+    pub total_trades: u64,
+    pub total_volume: i128,
+    pub realized_pnl: i128,
+    pub opened_at: u64,
+}
+
+pub fn get_starting_balance(env: &Env) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&symbol_short!("sim_bal"))
+        .unwrap_or(DEFAULT_STARTING_BALANCE)
+}
+
+pub fn get_account(env: &Env, learner: &Address) -> Option<SimAccount> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::SimAccount(learner.clone()))
+}
+
+pub fn get_position(env: &Env, learner: &Address, pair: &Symbol) -> Position {
+    env.storage()
+        .persistent()
+        .get(&DataKey::SimPosition(learner.clone(), pair.clone()))
+        .unwrap_or(Position {
+            base_qty: 0,
+            avg_entry_price: 0,
+            realized_pnl: 0,
+            updated_at: 0,
+        })
+}
+
+pub fn get_stats(env: &Env) -> TradeStats {
+    env.storage()
+        .persistent()
+        .get(&symbol_short!("sim_stats"))
+        .unwrap_or(TradeStats {
+            total_trades: 0,
+            total_volume: 0,
+            last_trade_id: 0,
+        })
+}
+
+/// Open a sandbox funded with the configured starting balance
+pub fn open_account(env: &Env, learner: &Address) -> Result<SimAccount, TradeError> {
+    if get_account(env, learner).is_some() {
+        return Err(TradeError::SimAccountExists);
+    }
+
+    let account = SimAccount {
+        quote_balance: get_starting_balance(env),
+        pairs: Vec::new(env),
+        total_trades: 0,
+        total_volume: 0,
+        realized_pnl: 0,
+        opened_at: env.ledger().timestamp(),
+    };
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::SimAccount(learner.clone()), &account);
+
+    Ok(account)
+}
+
+/// Fill a simulated order against the learner's virtual balances.
+///
+/// Buys spend `amount * price` of quote balance; sells need the base
+/// quantity to be held (no simulated shorting).
+pub fn execute(
+    env: &Env,
+    learner: &Address,
+    pair: &Symbol,
+    amount: i128,
+    price: i128,
+    is_buy: bool,
+) -> Result<u64, TradeError> {
+    let mut account = get_account(env, learner).ok_or(TradeError::SimAccountNotFound)?;
+    let mut position = get_position(env, learner, pair);
+
+    let notional = amount.checked_mul(price).ok_or(TradeError::InvalidAmount)?;
+    if is_buy {
+        if account.quote_balance < notional {
+            return Err(TradeError::InsufficientSimBalance);
+        }
+        account.quote_balance -= notional;
+    } else {
+        if position.base_qty < amount {
+            return Err(TradeError::InsufficientSimBalance);
+        }
+        account.quote_balance += notional;
+    }
+
+    let pnl = positions::apply_fill(&mut position, amount, price, is_buy);
+    position.updated_at = env.ledger().timestamp();
+
+    if !account.pairs.contains(pair) {
+        account.pairs.push_back(pair.clone());
+    }
+    account.total_trades += 1;
+    account.total_volume += amount;
+    account.realized_pnl += pnl;
+
+    let mut stats = get_stats(env);
+    let trade_id = stats.last_trade_id + 1;
+    stats.total_trades += 1;
+    stats.total_volume += amount;
+    stats.last_trade_id = trade_id;
+
+    let trade = Trade {
+        id: trade_id,
+        trader: learner.clone(),
+        pair: pair.clone(),
+        amount,
+        price,
+        timestamp: env.ledger().timestamp(),
+        is_buy,
+    };
+
+    env.storage()
+        .persistent()
+        .set(&DataKey::SimPosition(learner.clone(), pair.clone()), &position);
+    env.storage()
+        .persistent()
+        .set(&DataKey::SimAccount(learner.clone()), &account);
+    env.storage().persistent().set(&DataKey::SimTrade(trade_id), &trade);
+    env.storage()
+        .persistent()
+        .set(&symbol_short!("sim_stats"), &stats);
+
+    Ok(trade_id)
+}
+
+/// Clear a learner's simulated positions and restore the starting balance
+pub fn reset_account(env: &Env, learner: &Address) -> Result<SimAccount, TradeError> {
+    let account = get_account(env, learner).ok_or(TradeError::SimAccountNotFound)?;
+
+    for pair in account.pairs.iter() {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::SimPosition(learner.clone(), pair));
+    }
+
+    env.storage()
+        .persistent()
+        .remove(&DataKey::SimAccount(learner.clone()));
+
+    open_account(env, learner)
+}
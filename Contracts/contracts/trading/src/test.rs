@@ -1,123 +1,101 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{Env, testutils::Address as _, Vec, symbol_short};
-use shared::governance::ProposalStatus;
-
-// We need to import the social rewards contract for testing
-// In a workspace, we can register the contract by its WASM, but here we can just register the struct if it's available.
-// Since it's a separate crate, we can't easily import the struct without adding it to dependencies.
-// However, for unit testing within `trading` crate, we can Mock the reward contract or use `register_contract_wasm` if we had the wasm.
-// 
-// Alternatively, we can define a mock contract HERE in the test module.
-
-#[contract]
-pub struct MockRewardContract;
-
-#[contractimpl]
-impl MockRewardContract {
-    pub fn add_reward(env: Env, user: Address, amount: i128) {
-        if amount <= 0 {
-            panic!("Invalid reward amount");
-        }
-        // Success
-    }
-}
+use soroban_sdk::{Env, testutils::Address as _, testutils::Events as _, testutils::Ledger as _, token, BytesN, IntoVal, Vec, symbol_short};
+use shared::governance::voting::{VotingProposalV6, MAX_VOTERS};
+use shared::governance::{ProposalStatus, ProposalV3, TimelockBounds, UpgradeProposalV1, UpgradeProposalV3, ROLE_CHANGE_DELAY};
+use shared::oracle::Asset;
+use mock_oracle::{MockOracle, MockOracleClient};
+
+const WASM_V1: &[u8] = include_bytes!("../testdata/version_v1.wasm");
+const WASM_V2: &[u8] = include_bytes!("../testdata/version_v2.wasm");
 
 #[test]
 fn test_contract_initialization() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
 
-    let admin = Address::random(&env);
-    let approver1 = Address::random(&env);
-    let approver2 = Address::random(&env);
-    let executor = Address::random(&env);
+    let admin = Address::generate(&env);
+    let approver1 = Address::generate(&env);
+    let approver2 = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver1.clone());
     approvers.push_back(approver2.clone());
 
-    let result = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers.clone(),
-        executor.clone(),
-    );
-
+    let result = client.try_init(&admin, &approvers, &executor);
     assert!(result.is_ok());
 
     // Verify version is set
-    let version = UpgradeableTradingContract::get_version(env);
-    assert_eq!(version, 1);
+    let version = client.get_version();
+    assert_eq!(version, 7);
 }
 
 #[test]
 fn test_contract_cannot_be_initialized_twice() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
 
-    let admin = Address::random(&env);
-    let approver = Address::random(&env);
-    let executor = Address::random(&env);
+    let admin = Address::generate(&env);
+    let approver = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver);
 
     // First initialization should succeed
-    let result1 = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers.clone(),
-        executor.clone(),
-    );
+    let result1 = client.try_init(&admin, &approvers, &executor);
     assert!(result1.is_ok());
 
     // Second initialization should fail
-    let result2 = UpgradeableTradingContract::init(env, admin, approvers, executor);
+    let result2 = client.try_init(&admin, &approvers, &executor);
     assert!(result2.is_err());
 }
 
 #[test]
 fn test_upgrade_proposal_creation() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
 
-    let admin = Address::random(&env);
-    let approver = Address::random(&env);
-    let executor = Address::random(&env);
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let approver = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver.clone());
 
     // Initialize contract
-    let _ = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers.clone(),
-        executor.clone(),
-    );
+    client.init(&admin, &approvers, &executor);
 
     // Propose an upgrade
-    let new_hash = symbol_short!("v2hash");
+    let new_hash = BytesN::from_array(&env, &[2; 32]);
     let description = symbol_short!("Upgrade");
-    let result = UpgradeableTradingContract::propose_upgrade(
-        env.clone(),
-        admin.clone(),
-        new_hash,
-        description,
-        approvers.clone(),
-        1,
-        3600, // 1 hour timelock
+    let result = client.try_propose_upgrade(
+        &admin,
+        &new_hash,
+        &description,
+        &approvers,
+        &1,
+        &3600, // 1 hour timelock
     );
 
-    assert!(result.is_ok());
-    assert_eq!(result.unwrap(), 1); // First proposal ID
+    assert_eq!(result, Ok(Ok(1))); // First proposal ID
 
     // Get proposal details
-    let proposal = UpgradeableTradingContract::get_upgrade_proposal(env, 1);
-    assert!(proposal.is_ok());
-    let prop = proposal.unwrap();
+    let prop = client.get_upgrade_proposal(&1);
     assert_eq!(prop.id, 1);
     assert_eq!(prop.approvals_count, 0);
     assert_eq!(prop.status, ProposalStatus::Pending);
@@ -126,52 +104,49 @@ fn test_upgrade_proposal_creation() {
 #[test]
 fn test_upgrade_proposal_approval_flow() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
 
-    let admin = Address::random(&env);
-    let approver1 = Address::random(&env);
-    let approver2 = Address::random(&env);
-    let executor = Address::random(&env);
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let approver1 = Address::generate(&env);
+    let approver2 = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver1.clone());
     approvers.push_back(approver2.clone());
 
     // Initialize contract
-    let _ = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers.clone(),
-        executor.clone(),
-    );
+    client.init(&admin, &approvers, &executor);
 
     // Propose an upgrade with 2 approvals required
-    let new_hash = symbol_short!("v2hash");
+    let new_hash = BytesN::from_array(&env, &[2; 32]);
     let description = symbol_short!("Upgrade");
-    let proposal_id = UpgradeableTradingContract::propose_upgrade(
-        env.clone(),
-        admin.clone(),
-        new_hash,
-        description,
-        approvers.clone(),
-        2, // Need 2 approvals
-        3600,
-    )
-    .unwrap();
+    let proposal_id = client.propose_upgrade(
+        &admin,
+        &new_hash,
+        &description,
+        &approvers,
+        &2, // Need 2 approvals
+        &3600,
+    );
 
     // First approval
-    let result1 = UpgradeableTradingContract::approve_upgrade(env.clone(), proposal_id, approver1.clone());
+    let result1 = client.try_approve_upgrade(&proposal_id, &approver1);
     assert!(result1.is_ok());
 
-    let prop = UpgradeableTradingContract::get_upgrade_proposal(env.clone(), proposal_id).unwrap();
+    let prop = client.get_upgrade_proposal(&proposal_id);
     assert_eq!(prop.approvals_count, 1);
     assert_eq!(prop.status, ProposalStatus::Pending); // Still pending, need one more
 
     // Second approval
-    let result2 = UpgradeableTradingContract::approve_upgrade(env.clone(), proposal_id, approver2.clone());
+    let result2 = client.try_approve_upgrade(&proposal_id, &approver2);
     assert!(result2.is_ok());
 
-    let prop = UpgradeableTradingContract::get_upgrade_proposal(env.clone(), proposal_id).unwrap();
+    let prop = client.get_upgrade_proposal(&proposal_id);
     assert_eq!(prop.approvals_count, 2);
     assert_eq!(prop.status, ProposalStatus::Approved); // Now approved!
 }
@@ -179,59 +154,51 @@ fn test_upgrade_proposal_approval_flow() {
 #[test]
 fn test_upgrade_timelock_enforcement() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
 
-    let admin = Address::random(&env);
-    let approver = Address::random(&env);
-    let executor = Address::random(&env);
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let approver = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver.clone());
 
     // Initialize contract
-    let _ = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers.clone(),
-        executor.clone(),
-    );
+    client.init(&admin, &approvers, &executor);
 
     // Propose an upgrade with 4-hour timelock
-    let proposal_id = UpgradeableTradingContract::propose_upgrade(
-        env.clone(),
-        admin.clone(),
-        symbol_short!("v2hash"),
-        symbol_short!("Upgrade"),
-        approvers,
-        1,
-        14400, // 4 hours = 14400 seconds
-    )
-    .unwrap();
+    let proposal_id = client.propose_upgrade(
+        &admin,
+        &env.deployer().upload_contract_wasm(WASM_V2),
+        &symbol_short!("Upgrade"),
+        &approvers,
+        &1,
+        &14400, // 4 hours = 14400 seconds
+    );
 
     // Approve the proposal
-    let _ = UpgradeableTradingContract::approve_upgrade(env.clone(), proposal_id, approver);
+    client.approve_upgrade(&proposal_id, &approver);
 
     // Try to execute immediately (should fail)
-    let execute_result = UpgradeableTradingContract::execute_upgrade(
-        env.clone(),
-        proposal_id,
-        executor.clone(),
-    );
-    assert!(execute_result.is_err()); // Should fail - timelock not expired
+    let execute_result = client.try_execute_upgrade(&proposal_id, &executor);
+    assert_eq!(execute_result, Err(Ok(GovernanceError::TimelockNotExpired)));
 
     // Advance time to after timelock
-    env.ledger().set_timestamp(1000 + 14401); // Past the 4-hour mark
+    env.ledger().with_mut(|li| li.timestamp = 1000 + 14401); // Past the 4-hour mark
 
     // Now execution should succeed
-    let execute_result = UpgradeableTradingContract::execute_upgrade(
-        env.clone(),
-        proposal_id,
-        executor,
-    );
+    let execute_result = client.try_execute_upgrade(&proposal_id, &executor);
     assert!(execute_result.is_ok());
 
-    // Verify proposal is marked as executed
-    let prop = UpgradeableTradingContract::get_upgrade_proposal(env, proposal_id).unwrap();
+    // Verify proposal is marked as executed; the contract now runs the new
+    // code, so read its storage directly
+    let prop = env
+        .as_contract(&contract_id, || GovernanceManager::get_proposal(&env, proposal_id))
+        .unwrap();
     assert_eq!(prop.status, ProposalStatus::Executed);
     assert!(prop.executed);
 }
@@ -239,95 +206,93 @@ fn test_upgrade_timelock_enforcement() {
 #[test]
 fn test_upgrade_rejection_flow() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
 
-    let admin = Address::random(&env);
-    let approver = Address::random(&env);
-    let executor = Address::random(&env);
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let approver = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver.clone());
 
     // Initialize contract
-    let _ = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers.clone(),
-        executor.clone(),
-    );
+    client.init(&admin, &approvers, &executor);
 
     // Propose an upgrade
-    let proposal_id = UpgradeableTradingContract::propose_upgrade(
-        env.clone(),
-        admin.clone(),
-        symbol_short!("v2hash"),
-        symbol_short!("Upgrade"),
-        approvers,
-        1,
-        3600,
-    )
-    .unwrap();
+    let proposal_id = client.propose_upgrade(
+        &admin,
+        &BytesN::from_array(&env, &[2; 32]),
+        &symbol_short!("Upgrade"),
+        &approvers,
+        &1,
+        &3600,
+    );
 
     // Reject the proposal
-    let result = UpgradeableTradingContract::reject_upgrade(env.clone(), proposal_id, approver);
+    let result = client.try_reject_upgrade(&proposal_id, &approver);
     assert!(result.is_ok());
 
     // Verify status is rejected
-    let prop = UpgradeableTradingContract::get_upgrade_proposal(env, proposal_id).unwrap();
+    let prop = client.get_upgrade_proposal(&proposal_id);
     assert_eq!(prop.status, ProposalStatus::Rejected);
 }
 
 #[test]
 fn test_upgrade_cancellation_by_admin() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
 
-    let admin = Address::random(&env);
-    let approver = Address::random(&env);
-    let executor = Address::random(&env);
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let approver = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver.clone());
 
     // Initialize contract
-    let _ = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers.clone(),
-        executor.clone(),
-    );
+    client.init(&admin, &approvers, &executor);
 
     // Propose an upgrade
-    let proposal_id = UpgradeableTradingContract::propose_upgrade(
-        env.clone(),
-        admin.clone(),
-        symbol_short!("v2hash"),
-        symbol_short!("Upgrade"),
-        approvers,
-        1,
-        3600,
-    )
-    .unwrap();
+    let proposal_id = client.propose_upgrade(
+        &admin,
+        &BytesN::from_array(&env, &[2; 32]),
+        &symbol_short!("Upgrade"),
+        &approvers,
+        &1,
+        &3600,
+    );
 
     // Admin can cancel at any time
-    let result = UpgradeableTradingContract::cancel_upgrade(env.clone(), proposal_id, admin);
+    let result = client.try_cancel_upgrade(&proposal_id, &admin);
     assert!(result.is_ok());
 
     // Verify status is cancelled
-    let prop = UpgradeableTradingContract::get_upgrade_proposal(env, proposal_id).unwrap();
+    let prop = client.get_upgrade_proposal(&proposal_id);
     assert_eq!(prop.status, ProposalStatus::Cancelled);
 }
 
 #[test]
 fn test_multi_sig_protection() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
 
-    let admin = Address::random(&env);
-    let approver1 = Address::random(&env);
-    let approver2 = Address::random(&env);
-    let approver3 = Address::random(&env);
-    let executor = Address::random(&env);
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let approver1 = Address::generate(&env);
+    let approver2 = Address::generate(&env);
+    let approver3 = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver1.clone());
@@ -335,38 +300,31 @@ fn test_multi_sig_protection() {
     approvers.push_back(approver3.clone());
 
     // Initialize contract
-    let _ = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers.clone(),
-        executor.clone(),
-    );
+    client.init(&admin, &approvers, &executor);
 
     // Propose with 2 of 3 multi-sig requirement
-    let proposal_id = UpgradeableTradingContract::propose_upgrade(
-        env.clone(),
-        admin.clone(),
-        symbol_short!("v2hash"),
-        symbol_short!("Upgrade"),
-        approvers,
-        2, // 2 of 3 required
-        3600,
-    )
-    .unwrap();
+    let proposal_id = client.propose_upgrade(
+        &admin,
+        &BytesN::from_array(&env, &[2; 32]),
+        &symbol_short!("Upgrade"),
+        &approvers,
+        &2, // 2 of 3 required
+        &3600,
+    );
 
     // Get initial proposal
-    let prop = UpgradeableTradingContract::get_upgrade_proposal(env.clone(), proposal_id).unwrap();
+    let prop = client.get_upgrade_proposal(&proposal_id);
     assert_eq!(prop.approval_threshold, 2);
 
     // First approver approves
-    let _ = UpgradeableTradingContract::approve_upgrade(env.clone(), proposal_id, approver1);
-    let prop = UpgradeableTradingContract::get_upgrade_proposal(env.clone(), proposal_id).unwrap();
+    client.approve_upgrade(&proposal_id, &approver1);
+    let prop = client.get_upgrade_proposal(&proposal_id);
     assert_eq!(prop.approvals_count, 1);
     assert_eq!(prop.status, ProposalStatus::Pending); // Not enough yet
 
     // Second approver approves
-    let _ = UpgradeableTradingContract::approve_upgrade(env.clone(), proposal_id, approver2);
-    let prop = UpgradeableTradingContract::get_upgrade_proposal(env.clone(), proposal_id).unwrap();
+    client.approve_upgrade(&proposal_id, &approver2);
+    let prop = client.get_upgrade_proposal(&proposal_id);
     assert_eq!(prop.approvals_count, 2);
     assert_eq!(prop.status, ProposalStatus::Approved); // Now approved!
 
@@ -377,145 +335,2597 @@ fn test_multi_sig_protection() {
 #[test]
 fn test_duplicate_approval_prevention() {
     let env = Env::default();
-    env.ledger().set_timestamp(1000);
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
 
-    let admin = Address::random(&env);
-    let approver = Address::random(&env);
-    let executor = Address::random(&env);
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let approver = Address::generate(&env);
+    let executor = Address::generate(&env);
 
     let mut approvers = Vec::new(&env);
     approvers.push_back(approver.clone());
 
     // Initialize contract
-    let _ = UpgradeableTradingContract::init(
-        env.clone(),
-        admin.clone(),
-        approvers,
-        executor,
-    );
+    client.init(&admin, &approvers, &executor);
 
     // Propose an upgrade
-    let proposal_id = UpgradeableTradingContract::propose_upgrade(
-        env.clone(),
-        admin.clone(),
-        symbol_short!("v2hash"),
-        symbol_short!("Upgrade"),
-        vec![approver.clone()],
-        1,
-        3600,
-    )
-    .unwrap();
+    let proposal_id = client.propose_upgrade(
+        &admin,
+        &BytesN::from_array(&env, &[2; 32]),
+        &symbol_short!("Upgrade"),
+        &soroban_sdk::vec![&env, approver.clone()],
+        &1,
+        &3600,
+    );
 
     // First approval should succeed
-    let result1 = UpgradeableTradingContract::approve_upgrade(
-        env.clone(),
-        proposal_id,
-        approver.clone(),
-    );
+    let result1 = client.try_approve_upgrade(&proposal_id, &approver);
     assert!(result1.is_ok());
 
     // Second approval from same address should fail
-    let result2 = UpgradeableTradingContract::approve_upgrade(
-        env,
-        proposal_id,
-        approver,
-    );
-    assert!(result2.is_err()); // Cannot approve twice
+    let result2 = client.try_approve_upgrade(&proposal_id, &approver);
+    assert_eq!(result2, Err(Ok(GovernanceError::InvalidProposal))); // Already approved at threshold 1
 }
 
 #[test]
-fn test_trade_and_reward_success() {
+fn test_trade_pays_fee_to_recipient() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let trading_id = env.register_contract(None, TradingContract);
-    let trading_client = TradingContractClient::new(&env, &trading_id);
+    let (client, _) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
 
-    let reward_id = env.register_contract(None, MockRewardContract);
-    
-    // Setup Tokens
-    let issuer = Address::generate(&env);
-    let token_id = env.register_stellar_asset_contract(issuer);
-    let token_client = token::Client::new(&env, &token_id);
-    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    token_admin.mint(&trader, &1000);
+
+    let id = client.trade(&trader, &pair, &10, &100, &true, &token_id, &100, &recipient, &None);
+    assert_eq!(id, 1);
+
+    // Verify fee was paid
+    assert_eq!(token_client.balance(&trader), 900);
+    assert_eq!(token_client.balance(&recipient), 100);
+}
+
+#[test]
+fn test_failed_batch_rolls_back_collected_fees() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
 
     let trader = Address::generate(&env);
     let recipient = Address::generate(&env);
-    let fee = 100;
+    token_admin.mint(&trader, &1000);
+
+    // The first trade pays its fee before the second one fails
+    let fee = TradeFee { token: token_id.clone(), amount: 100, recipient: recipient.clone() };
+    let requests = soroban_sdk::vec![
+        &env,
+        trade_request(&pair, 10, &fee),
+        trade_request(&pair, 0, &fee),
+    ];
+    let result = client.try_trade_batch(&trader, &requests, &BatchMode::AllOrNothing);
+    assert_eq!(result, Err(Ok(TradeError::InvalidAmount)));
+
+    // The fee transfer is rolled back with the rest of the batch
+    assert_eq!(token_client.balance(&trader), 1000);
+    assert_eq!(token_client.balance(&recipient), 0);
+    assert_eq!(client.get_stats().total_trades, 0);
+}
+
+fn setup_trading(env: &Env) -> (UpgradeableTradingContractClient<'_>, Address) {
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(env, &contract_id);
 
+    let admin = Address::generate(env);
+    let executor = Address::generate(env);
+    let approvers = soroban_sdk::vec![env, Address::generate(env)];
+    client.init(&admin, &approvers, &executor);
+
+    (client, admin)
+}
+
+fn setup_governance(
+    env: &Env,
+    approver_count: u32,
+) -> (UpgradeableTradingContractClient<'_>, Address, Vec<Address>, Address) {
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    let executor = Address::generate(env);
+    let mut approvers = Vec::new(env);
+    for _ in 0..approver_count {
+        approvers.push_back(Address::generate(env));
+    }
+    client.init(&admin, &approvers, &executor);
+
+    (client, admin, approvers, executor)
+}
+
+fn setup_fee_token(env: &Env) -> (Address, token::Client<'_>, token::StellarAssetClient<'_>) {
+    let issuer = Address::generate(env);
+    let token_id = env.register_stellar_asset_contract(issuer);
+    let token_client = token::Client::new(env, &token_id);
+    let token_admin = token::StellarAssetClient::new(env, &token_id);
+
+    (token_id, token_client, token_admin)
+}
+
+#[test]
+fn test_referral_fee_share_paid_to_referrer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+
+    let trader = Address::generate(&env);
+    let referrer = Address::generate(&env);
+    let recipient = Address::generate(&env);
     token_admin.mint(&trader, &1000);
 
-    // Run trade_and_reward
-    let res = trading_client.trade_and_reward(
-        &trader, 
-        &token_id, 
-        &fee, 
-        &recipient, 
-        &reward_id, 
-        &50 // valid reward
+    client.set_referral_share(&admin, &2000); // 20%
+    client.register_referrer(&trader, &referrer);
+    assert_eq!(client.get_referrer(&trader), Some(referrer.clone()));
+
+    client.trade(
+        &trader,
+        &symbol_short!("XLMUSDC"),
+        &500,
+        &10,
+        &true,
+        &token_id,
+        &100,
+        &recipient,
+        &None,
     );
 
-    assert!(res.is_ok());
-
-    // Verify fee was paid
     assert_eq!(token_client.balance(&trader), 900);
+    assert_eq!(token_client.balance(&referrer), 20);
+    assert_eq!(token_client.balance(&recipient), 80);
+
+    let stats = client.get_referral_stats(&referrer);
+    assert_eq!(stats.referred_traders, 1);
+    assert_eq!(stats.referred_trades, 1);
+    assert_eq!(stats.referred_volume, 500);
+    assert_eq!(client.get_referral_rewards(&referrer, &token_id), 20);
+}
+
+#[test]
+fn test_trade_without_referrer_pays_full_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    token_admin.mint(&trader, &1000);
+
+    client.set_referral_share(&admin, &2000);
+    client.trade(
+        &trader,
+        &symbol_short!("XLMUSDC"),
+        &500,
+        &10,
+        &false,
+        &token_id,
+        &100,
+        &recipient,
+        &None,
+    );
+
     assert_eq!(token_client.balance(&recipient), 100);
 }
 
 #[test]
-fn test_trade_and_reward_atomic_rollback() {
+fn test_referrer_registration_rules() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let trading_id = env.register_contract(None, TradingContract);
-    let trading_client = TradingContractClient::new(&env, &trading_id);
+    let (client, admin) = setup_trading(&env);
+    let trader = Address::generate(&env);
+    let referrer = Address::generate(&env);
+
+    // Cannot refer yourself
+    let result = client.try_register_referrer(&trader, &trader);
+    assert_eq!(result, Err(Ok(TradeError::SelfReferral)));
 
-    let reward_id = env.register_contract(None, MockRewardContract);
-    
-    // Setup Tokens
-    let issuer = Address::generate(&env);
-    let token_id = env.register_stellar_asset_contract(issuer);
-    let token_client = token::Client::new(&env, &token_id);
-    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+    // Referrer can only be set once
+    client.register_referrer(&trader, &referrer);
+    let result = client.try_register_referrer(&trader, &Address::generate(&env));
+    assert_eq!(result, Err(Ok(TradeError::ReferrerAlreadySet)));
+
+    // Share cannot exceed 100%
+    let result = client.try_set_referral_share(&admin, &10_001);
+    assert_eq!(result, Err(Ok(TradeError::InvalidFeeShare)));
+
+    // Only admin can configure the share
+    let result = client.try_set_referral_share(&trader, &100);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+}
+
+#[test]
+fn test_fee_accrual_and_withdrawal() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+
+    let trader = Address::generate(&env);
+    let referrer = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    token_admin.mint(&trader, &1000);
+
+    client.set_fee_mode(&admin, &FeeMode::Accrue);
+    client.set_referral_share(&admin, &1000); // 10%
+    client.register_referrer(&trader, &referrer);
+
+    for _ in 0..3 {
+        client.trade(
+            &trader,
+            &symbol_short!("XLMUSDC"),
+            &100,
+            &10,
+            &true,
+            &token_id,
+            &100,
+            &treasury,
+            &None,
+        );
+    }
+
+    // Fees sit in the contract until withdrawn
+    assert_eq!(token_client.balance(&trader), 700);
+    assert_eq!(token_client.balance(&treasury), 0);
+    assert_eq!(token_client.balance(&client.address), 300);
+    assert_eq!(client.get_accrued_fees(&treasury, &token_id), 270);
+    assert_eq!(client.get_accrued_fees(&referrer, &token_id), 30);
+    assert_eq!(client.get_total_accrued_fees(&token_id), 300);
+
+    assert_eq!(client.withdraw_fees(&treasury, &token_id), 270);
+    assert_eq!(token_client.balance(&treasury), 270);
+    assert_eq!(client.get_accrued_fees(&treasury, &token_id), 0);
+    assert_eq!(client.get_total_accrued_fees(&token_id), 30);
+
+    // Nothing left to withdraw
+    let result = client.try_withdraw_fees(&treasury, &token_id);
+    assert_eq!(result, Err(Ok(FeeError::NothingToWithdraw)));
+}
+
+fn setup_oracle_pair<'a>(
+    env: &'a Env,
+    client: &UpgradeableTradingContractClient,
+    admin: &Address,
+    pair: &Symbol,
+) -> MockOracleClient<'a> {
+    let oracle_id = env.register_contract(None, MockOracle);
+    let oracle = MockOracleClient::new(env, &oracle_id);
+
+    client.set_pair_oracle(
+        admin,
+        pair,
+        &PairOracle {
+            oracle: oracle_id,
+            asset: Asset::Other(pair.clone()),
+            max_deviation_bps: 500, // 5%
+            max_age: 300,
+        },
+    );
+
+    oracle
+}
+
+#[test]
+fn test_trade_price_checked_against_oracle() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 10_000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let oracle = setup_oracle_pair(&env, &client, &admin, &pair);
+
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    token_admin.mint(&trader, &1000);
+
+    oracle.set_price(&Asset::Other(pair.clone()), &1000, &9_900);
+
+    // Within 5% of the oracle price
+    client.trade(&trader, &pair, &10, &1040, &true, &token_id, &1, &recipient, &None);
+
+    // More than 5% away from the oracle price
+    let result = client.try_trade(&trader, &pair, &10, &1060, &true, &token_id, &1, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::PriceDeviation)));
+    let result = client.try_trade(&trader, &pair, &10, &940, &false, &token_id, &1, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::PriceDeviation)));
+
+    // Extreme oracle readings are rejected instead of overflowing
+    oracle.set_price(&Asset::Other(pair.clone()), &i128::MAX, &9_900);
+    let result = client.try_trade(&trader, &pair, &10, &1, &true, &token_id, &1, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::PriceDeviation)));
+    oracle.set_price(&Asset::Other(pair.clone()), &1000, &9_900);
+
+    // Oracle price older than max_age
+    env.ledger().with_mut(|li| li.timestamp = 10_500);
+    let result = client.try_trade(&trader, &pair, &10, &1000, &true, &token_id, &1, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::StalePrice)));
+
+    assert_eq!(client.get_stats().total_trades, 1);
+}
+
+#[test]
+fn test_trade_rejected_when_oracle_has_no_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    setup_oracle_pair(&env, &client, &admin, &pair);
+
+    let trader = Address::generate(&env);
+    token_admin.mint(&trader, &1000);
+
+    let result = client.try_trade(
+        &trader,
+        &pair,
+        &10,
+        &1000,
+        &true,
+        &token_id,
+        &1,
+        &Address::generate(&env),
+        &None,
+    );
+    assert_eq!(result, Err(Ok(TradeError::OracleUnavailable)));
+
+    // Removing the guard lets any price through again
+    client.remove_pair_oracle(&admin, &pair);
+    client.trade(&trader, &pair, &10, &1000, &true, &token_id, &1, &Address::generate(&env), &None);
+}
+
+#[test]
+fn test_circuit_breaker_halts_only_affected_pair() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let other_pair = symbol_short!("BTCUSDC");
 
     let trader = Address::generate(&env);
     let recipient = Address::generate(&env);
-    let fee = 100;
+    token_admin.mint(&trader, &1000);
+
+    client.set_circuit_breaker(
+        &admin,
+        &pair,
+        &CircuitBreakerConfig {
+            max_move_bps: 1000, // 10%
+            window: 600,
+            cooldown: 3600,
+        },
+    );
+
+    // First trade sets the reference price, a 5% move is fine
+    client.trade(&trader, &pair, &10, &100, &true, &token_id, &1, &recipient, &None);
+    client.trade(&trader, &pair, &10, &105, &true, &token_id, &1, &recipient, &None);
+
+    // A 20% move trips the breaker; the tripping trade itself executes
+    client.trade(&trader, &pair, &10, &120, &true, &token_id, &1, &recipient, &None);
+    let state = client.get_circuit_state(&pair).unwrap();
+    assert_eq!(state.halted_until, 1000 + 3600);
+
+    let result = client.try_trade(&trader, &pair, &10, &120, &true, &token_id, &1, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::PairHalted)));
 
+    // Other pairs keep trading
+    client.trade(&trader, &other_pair, &10, &500, &true, &token_id, &1, &recipient, &None);
+
+    // After the cooldown the pair reopens with a fresh reference price
+    env.ledger().with_mut(|li| li.timestamp = 1000 + 3600);
+    client.trade(&trader, &pair, &10, &120, &true, &token_id, &1, &recipient, &None);
+    let state = client.get_circuit_state(&pair).unwrap();
+    assert_eq!(state.reference_price, 120);
+    assert_eq!(state.halted_until, 0);
+}
+
+#[test]
+fn test_circuit_breaker_admin_reset() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
     token_admin.mint(&trader, &1000);
 
-    // Run trade_and_reward with INVALID reward amount (0)
-    // This will cause MockRewardContract to panic.
-    // TradingContract::trade_and_reward uses safe_invoke, which catches the panic
-    // and returns SafeCallErrors::CALL_FAILED (mapped to u32).
-    // The TradingContract then returns Err.
-    // The ENV should revert all changes, including the fee payment.
-    
-    // Note: We use try_trade_and_reward to inspect the error result
-    let res = trading_client.try_trade_and_reward(
-        &trader, 
-        &token_id, 
-        &fee, 
-        &recipient, 
-        &reward_id, 
-        &0 // Invalid reward amount -> panic
-    );
-
-    // The result should be an Err
-    assert!(res.is_err());
-    
-    // Check error code
-    match res {
-        Err(Ok(code)) => {
-             // We expect CALL_FAILED (2001)
-             assert_eq!(code, SafeCallErrors::CALL_FAILED);
+    client.set_circuit_breaker(
+        &admin,
+        &pair,
+        &CircuitBreakerConfig {
+            max_move_bps: 1000,
+            window: 600,
+            cooldown: 3600,
+        },
+    );
+
+    client.trade(&trader, &pair, &10, &100, &true, &token_id, &1, &recipient, &None);
+    client.trade(&trader, &pair, &10, &50, &false, &token_id, &1, &recipient, &None);
+    let result = client.try_trade(&trader, &pair, &10, &50, &false, &token_id, &1, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::PairHalted)));
+
+    // Only the admin can override
+    let result = client.try_reset_circuit_breaker(&trader, &pair);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+
+    client.reset_circuit_breaker(&admin, &pair);
+    assert_eq!(client.get_circuit_state(&pair), None);
+    client.trade(&trader, &pair, &10, &50, &false, &token_id, &1, &recipient, &None);
+
+    // Invalid thresholds are rejected
+    let result = client.try_set_circuit_breaker(
+        &admin,
+        &pair,
+        &CircuitBreakerConfig {
+            max_move_bps: 0,
+            window: 600,
+            cooldown: 3600,
         },
-        _ => panic!("Expected contract error code"),
+    );
+    assert_eq!(result, Err(Ok(TradeError::InvalidCircuitConfig)));
+}
+
+#[test]
+fn test_position_and_pnl_tracking() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, _, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    token_admin.mint(&trader, &1000);
+
+    // Buy 10 @ 100 and 10 @ 200: long 20 @ 150
+    client.trade(&trader, &pair, &10, &100, &true, &token_id, &0, &recipient, &None);
+    client.trade(&trader, &pair, &10, &200, &true, &token_id, &0, &recipient, &None);
+    let position = client.get_position(&trader, &pair);
+    assert_eq!(position.base_qty, 20);
+    assert_eq!(position.avg_entry_price, 150);
+    assert_eq!(position.realized_pnl, 0);
+
+    // Sell 5 @ 170: realize 5 * 20
+    client.trade(&trader, &pair, &5, &170, &false, &token_id, &0, &recipient, &None);
+    let position = client.get_position(&trader, &pair);
+    assert_eq!(position.base_qty, 15);
+    assert_eq!(position.avg_entry_price, 150);
+    assert_eq!(position.realized_pnl, 100);
+
+    // Sell 25 @ 140: close 15 for -150 and open short 10 @ 140
+    client.trade(&trader, &pair, &25, &140, &false, &token_id, &0, &recipient, &None);
+    let position = client.get_position(&trader, &pair);
+    assert_eq!(position.base_qty, -10);
+    assert_eq!(position.avg_entry_price, 140);
+    assert_eq!(position.realized_pnl, -50);
+
+    // Buy back 10 @ 120: short profits 10 * 20 and the position is flat
+    client.trade(&trader, &pair, &10, &120, &true, &token_id, &0, &recipient, &None);
+    let position = client.get_position(&trader, &pair);
+    assert_eq!(position.base_qty, 0);
+    assert_eq!(position.avg_entry_price, 0);
+    assert_eq!(position.realized_pnl, 150);
+
+    let stats = client.get_trader_stats(&trader);
+    assert_eq!(stats.total_trades, 5);
+    assert_eq!(stats.total_volume, 60);
+    assert_eq!(stats.realized_pnl, 150);
+
+    // Other traders are unaffected
+    let other = Address::generate(&env);
+    assert_eq!(client.get_trader_stats(&other).total_trades, 0);
+    assert_eq!(client.get_position(&other, &pair).base_qty, 0);
+}
+
+#[test]
+fn test_trade_rejects_non_positive_amount_or_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, _, _) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    let result = client.try_trade(&trader, &pair, &0, &100, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::InvalidAmount)));
+    let result = client.try_trade(&trader, &pair, &10, &-1, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::InvalidAmount)));
+}
+
+#[test]
+fn test_pair_stats_and_candles() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 7_200); // Start of hour bucket 2
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, _, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let other_pair = symbol_short!("BTCUSDC");
+
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    token_admin.mint(&trader, &1000);
+
+    client.trade(&trader, &pair, &10, &100, &true, &token_id, &0, &recipient, &None);
+    env.ledger().with_mut(|li| li.timestamp = 7_300);
+    client.trade(&trader, &pair, &5, &120, &false, &token_id, &0, &recipient, &None);
+    env.ledger().with_mut(|li| li.timestamp = 7_400);
+    client.trade(&trader, &pair, &20, &90, &true, &token_id, &0, &recipient, &None);
+    client.trade(&trader, &other_pair, &1, &5000, &true, &token_id, &0, &recipient, &None);
+
+    // Next hour
+    env.ledger().with_mut(|li| li.timestamp = 10_900);
+    client.trade(&trader, &pair, &7, &110, &true, &token_id, &0, &recipient, &None);
+
+    let stats = client.get_pair_stats(&pair);
+    assert_eq!(stats.total_trades, 4);
+    assert_eq!(stats.total_volume, 42);
+    assert_eq!(stats.last_price, 110);
+    assert_eq!(stats.last_trade_id, 5);
+    assert_eq!(client.get_pair_stats(&other_pair).total_trades, 1);
+
+    let hourly = client.get_candles(&pair, &Interval::Hour, &0, &10_900);
+    assert_eq!(hourly.len(), 2);
+    assert_eq!(
+        hourly.get(0).unwrap(),
+        Candle {
+            start: 7_200,
+            open: 100,
+            high: 120,
+            low: 90,
+            close: 90,
+            volume: 35,
+            trade_count: 3,
+        }
+    );
+    assert_eq!(hourly.get(1).unwrap().start, 10_800);
+    assert_eq!(hourly.get(1).unwrap().open, 110);
+
+    let daily = client.get_candles(&pair, &Interval::Day, &0, &10_900);
+    assert_eq!(daily.len(), 1);
+    let day = daily.get(0).unwrap();
+    assert_eq!((day.open, day.high, day.low, day.close), (100, 120, 90, 110));
+    assert_eq!(day.volume, 42);
+    assert_eq!(day.trade_count, 4);
+
+    // Query ranges are bounded
+    let result = client.try_get_candles(&pair, &Interval::Hour, &0, &(3_600 * MAX_CANDLE_RANGE));
+    assert_eq!(result, Err(Ok(TradeError::InvalidBucketRange)));
+    let result = client.try_get_candles(&pair, &Interval::Hour, &10_900, &0);
+    assert_eq!(result, Err(Ok(TradeError::InvalidBucketRange)));
+}
+
+#[test]
+fn test_paper_trading_uses_virtual_balances() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin) = setup_trading(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let learner = Address::generate(&env);
+
+    // Sandboxes are funded before an admin configures a balance
+    let account = client.sim_open_account(&learner);
+    assert_eq!(account.quote_balance, simulation::DEFAULT_STARTING_BALANCE);
+    assert_eq!(
+        client.try_set_sim_starting_balance(&admin, &0),
+        Err(Ok(TradeError::InvalidAmount))
+    );
+
+    let result = client.try_sim_open_account(&learner);
+    assert_eq!(result, Err(Ok(TradeError::SimAccountExists)));
+
+    // Buy 50 @ 100, sell 20 @ 110
+    client.sim_trade(&learner, &pair, &50, &100, &true);
+    client.sim_trade(&learner, &pair, &20, &110, &false);
+
+    let account = client.get_sim_account(&learner).unwrap();
+    assert_eq!(account.quote_balance, 10_000 - 5_000 + 2_200);
+    assert_eq!(account.total_trades, 2);
+    assert_eq!(account.realized_pnl, 200);
+
+    let position = client.get_sim_position(&learner, &pair);
+    assert_eq!(position.base_qty, 30);
+    assert_eq!(position.avg_entry_price, 100);
+
+    // Cannot overspend or sell what isn't held
+    let result = client.try_sim_trade(&learner, &pair, &1_000, &100, &true);
+    assert_eq!(result, Err(Ok(TradeError::InsufficientSimBalance)));
+    let result = client.try_sim_trade(&learner, &pair, &31, &100, &false);
+    assert_eq!(result, Err(Ok(TradeError::InsufficientSimBalance)));
+
+    // Simulated activity stays out of real statistics
+    assert_eq!(client.get_sim_stats().total_trades, 2);
+    assert_eq!(client.get_stats().total_trades, 0);
+    assert_eq!(client.get_pair_stats(&pair).total_trades, 0);
+    assert_eq!(client.get_trader_stats(&learner).total_trades, 0);
+    assert_eq!(client.get_position(&learner, &pair).base_qty, 0);
+}
+
+#[test]
+fn test_paper_trading_reset_and_pricing_checks() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin) = setup_trading(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let oracle = setup_oracle_pair(&env, &client, &admin, &pair);
+    oracle.set_price(&Asset::Other(pair.clone()), &100, &1_000);
+
+    let learner = Address::generate(&env);
+    let result = client.try_sim_trade(&learner, &pair, &1, &100, &true);
+    assert_eq!(result, Err(Ok(TradeError::SimAccountNotFound)));
+
+    client.set_sim_starting_balance(&admin, &1_000);
+    client.sim_open_account(&learner);
+
+    // Same oracle band as real trades
+    let result = client.try_sim_trade(&learner, &pair, &1, &200, &true);
+    assert_eq!(result, Err(Ok(TradeError::PriceDeviation)));
+
+    client.sim_trade(&learner, &pair, &5, &100, &true);
+    assert_eq!(client.get_sim_account(&learner).unwrap().quote_balance, 500);
+
+    // Only the admin can reset a sandbox
+    let result = client.try_sim_reset(&learner, &learner);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+
+    let account = client.sim_reset(&admin, &learner);
+    assert_eq!(account.quote_balance, 1_000);
+    assert_eq!(account.total_trades, 0);
+    assert_eq!(client.get_sim_position(&learner, &pair).base_qty, 0);
+}
+
+fn competition_config(
+    env: &Env,
+    pair: &Symbol,
+    scoring: ScoringRule,
+    prize_token: &Address,
+) -> CompetitionConfig {
+    CompetitionConfig {
+        name: symbol_short!("cohort1"),
+        start_time: 1_000,
+        end_time: 2_000,
+        pairs: soroban_sdk::vec![env, pair.clone()],
+        scoring,
+        prize_token: prize_token.clone(),
+        prize_pool: 1_000,
+        payout_bps: soroban_sdk::vec![env, 6_000, 3_000], // 60% / 30%, 10% returned
+        max_participants: 10,
     }
+}
 
-    // CRITICAL: Verify ATOMICITY
-    // The fee transfer (which happened before the cross-call) must be rolled back.
-    // Trader balance should still be 1000.
-    assert_eq!(token_client.balance(&trader), 1000);
-    assert_eq!(token_client.balance(&recipient), 0);
+#[test]
+fn test_competition_volume_leaderboard_and_payout() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 500);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let recipient = Address::generate(&env);
+    token_admin.mint(&admin, &1_000);
+
+    let id = client.create_competition(
+        &admin,
+        &competition_config(&env, &pair, ScoringRule::Volume, &token_id),
+    );
+    assert_eq!(token_client.balance(&admin), 0);
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let outsider = Address::generate(&env);
+    for trader in [&alice, &bob, &carol] {
+        client.join_competition(trader, &id);
+    }
+    let result = client.try_join_competition(&alice, &id);
+    assert_eq!(result, Err(Ok(TradeError::AlreadyRegistered)));
+
+    // Trades before the start don't count
+    client.trade(&alice, &pair, &500, &10, &true, &token_id, &0, &recipient, &None);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_500);
+    client.trade(&alice, &pair, &10, &10, &true, &token_id, &0, &recipient, &None);
+    client.trade(&bob, &pair, &30, &10, &true, &token_id, &0, &recipient, &None);
+    client.trade(&carol, &pair, &20, &10, &true, &token_id, &0, &recipient, &None);
+    client.trade(&outsider, &pair, &99, &10, &true, &token_id, &0, &recipient, &None);
+    // Ineligible pair
+    client.trade(&alice, &symbol_short!("BTCUSDC"), &99, &10, &true, &token_id, &0, &recipient, &None);
+
+    let board = client.get_leaderboard(&id);
+    assert_eq!(board.len(), 3);
+    assert_eq!(board.get(0).unwrap(), LeaderboardEntry { trader: bob.clone(), score: 30 });
+    assert_eq!(board.get(1).unwrap(), LeaderboardEntry { trader: carol.clone(), score: 20 });
+    assert_eq!(board.get(2).unwrap(), LeaderboardEntry { trader: alice.clone(), score: 10 });
+    assert_eq!(client.get_competition_score(&id, &outsider), None);
+
+    let result = client.try_finalize_competition(&id);
+    assert_eq!(result, Err(Ok(TradeError::CompetitionNotEnded)));
+
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    let payouts = client.finalize_competition(&id);
+    assert_eq!(payouts.len(), 2);
+    assert_eq!(token_client.balance(&bob), 600);
+    assert_eq!(token_client.balance(&carol), 300);
+    assert_eq!(token_client.balance(&alice), 0);
+    assert_eq!(token_client.balance(&admin), 100);
+    assert!(client.get_competition(&id).unwrap().finalized);
+
+    let result = client.try_finalize_competition(&id);
+    assert_eq!(result, Err(Ok(TradeError::CompetitionFinalized)));
+}
+
+#[test]
+fn test_competition_pnl_scoring() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let recipient = Address::generate(&env);
+    token_admin.mint(&admin, &1_000);
+
+    let id = client.create_competition(
+        &admin,
+        &competition_config(&env, &pair, ScoringRule::Pnl, &token_id),
+    );
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    client.join_competition(&alice, &id);
+    client.join_competition(&bob, &id);
+
+    // Alice gains 10 * 5, Bob loses 10 * 5
+    client.trade(&alice, &pair, &10, &100, &true, &token_id, &0, &recipient, &None);
+    client.trade(&alice, &pair, &10, &105, &false, &token_id, &0, &recipient, &None);
+    client.trade(&bob, &pair, &10, &100, &true, &token_id, &0, &recipient, &None);
+    client.trade(&bob, &pair, &10, &95, &false, &token_id, &0, &recipient, &None);
+
+    let board = client.get_leaderboard(&id);
+    assert_eq!(board.get(0).unwrap(), LeaderboardEntry { trader: alice.clone(), score: 50 });
+    assert_eq!(board.get(1).unwrap(), LeaderboardEntry { trader: bob.clone(), score: -50 });
+
+    // Registration closes at the end time
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    let result = client.try_join_competition(&Address::generate(&env), &id);
+    assert_eq!(result, Err(Ok(TradeError::CompetitionClosed)));
+
+    client.finalize_competition(&id);
+    assert_eq!(token_client.balance(&alice), 600);
+    assert_eq!(token_client.balance(&bob), 300);
+}
+
+#[test]
+fn test_competition_validation() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 500);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _, _) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let mut config = competition_config(&env, &pair, ScoringRule::Volume, &token_id);
+    config.prize_pool = 0;
+
+    // Payout shares above 100%
+    let mut bad = config.clone();
+    bad.payout_bps = soroban_sdk::vec![&env, 8_000, 3_000];
+    let result = client.try_create_competition(&admin, &bad);
+    assert_eq!(result, Err(Ok(TradeError::InvalidCompetition)));
+    bad.payout_bps = soroban_sdk::vec![&env, u32::MAX, 2];
+    let result = client.try_create_competition(&admin, &bad);
+    assert_eq!(result, Err(Ok(TradeError::InvalidCompetition)));
+
+    // End before start
+    let mut bad = config.clone();
+    bad.end_time = 900;
+    let result = client.try_create_competition(&admin, &bad);
+    assert_eq!(result, Err(Ok(TradeError::InvalidCompetition)));
+
+    // Only the admin can create competitions
+    let result = client.try_create_competition(&Address::generate(&env), &config);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+
+    // Registration is capped
+    config.max_participants = 1;
+    let id = client.create_competition(&admin, &config);
+    client.join_competition(&Address::generate(&env), &id);
+    let result = client.try_join_competition(&Address::generate(&env), &id);
+    assert_eq!(result, Err(Ok(TradeError::CompetitionFull)));
+
+    let result = client.try_join_competition(&Address::generate(&env), &99);
+    assert_eq!(result, Err(Ok(TradeError::CompetitionNotFound)));
+}
+
+
+fn order_request(
+    env: &Env,
+    pair: &Symbol,
+    kind: OrderKind,
+    is_buy: bool,
+    trigger_price: i128,
+    token: &Address,
+) -> OrderRequest {
+    OrderRequest {
+        pair: pair.clone(),
+        amount: 10,
+        is_buy,
+        kind,
+        trigger_price,
+        limit_price: 0,
+        trigger_source: TriggerSource::LastTrade,
+        expiry: 5_000,
+        fee: TradeFee {
+            token: token.clone(),
+            amount: 10,
+            recipient: Address::generate(env),
+        },
+        bounty: 5,
+    }
+}
+
+#[test]
+fn test_stop_loss_triggers_on_last_trade_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let asset = Asset::Other(pair.clone());
+
+    let owner = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let market = Address::generate(&env);
+    token_admin.mint(&owner, &100);
+
+    client.trade(&owner, &pair, &10, &100, &true, &token_id, &0, &market, &None);
+
+    let request = order_request(&env, &pair, OrderKind::StopMarket, false, 90, &token_id);
+    let recipient = request.fee.recipient.clone();
+    let id = client.place_order(&owner, &request);
+
+    // Fee and bounty are escrowed
+    assert_eq!(token_client.balance(&owner), 85);
+    assert_eq!(token_client.balance(&client.address), 15);
+
+    // Without an oracle band a single trade could fire the stop
+    client.trade(&market, &pair, &1, &1, &true, &token_id, &0, &market, &None);
+    let result = client.try_execute_order(&keeper, &id);
+    assert_eq!(result, Err(Ok(TradeError::NoReferencePrice)));
+
+    let oracle = setup_oracle_pair(&env, &client, &admin, &pair);
+    oracle.set_price(&asset, &100, &1_000);
+
+    // Price has not fallen to the stop yet
+    client.trade(&market, &pair, &1, &95, &true, &token_id, &0, &market, &None);
+    let result = client.try_execute_order(&keeper, &id);
+    assert_eq!(result, Err(Ok(TradeError::TriggerNotReached)));
+
+    oracle.set_price(&asset, &90, &1_000);
+    client.trade(&market, &pair, &1, &88, &true, &token_id, &0, &market, &None);
+    let trade_id = client.execute_order(&keeper, &id);
+
+    let order = client.get_order(&id).unwrap();
+    assert_eq!(order.status, OrderStatus::Executed);
+    assert_eq!(order.trade_id, trade_id);
+
+    // Sold at the triggering price and paid out of escrow
+    let position = client.get_position(&owner, &pair);
+    assert_eq!(position.base_qty, 0);
+    assert_eq!(position.realized_pnl, -120);
+    assert_eq!(token_client.balance(&keeper), 5);
+    assert_eq!(token_client.balance(&recipient), 10);
+    assert_eq!(token_client.balance(&client.address), 0);
+
+    let result = client.try_execute_order(&keeper, &id);
+    assert_eq!(result, Err(Ok(TradeError::OrderNotOpen)));
+}
+
+#[test]
+fn test_take_profit_and_stop_limit_trigger_on_oracle_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let oracle = setup_oracle_pair(&env, &client, &admin, &pair);
+    let asset = Asset::Other(pair.clone());
+
+    let owner = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    token_admin.mint(&owner, &100);
+
+    // Take profit on a long: sell once the price rises to 110
+    let mut take_profit = order_request(&env, &pair, OrderKind::TakeProfit, false, 110, &token_id);
+    take_profit.trigger_source = TriggerSource::Oracle;
+    let tp_id = client.place_order(&owner, &take_profit);
+
+    // Stop-limit buy: triggers at 120 but will not pay more than 125
+    let mut stop_limit = order_request(&env, &pair, OrderKind::StopLimit, true, 120, &token_id);
+    stop_limit.trigger_source = TriggerSource::Oracle;
+    stop_limit.limit_price = 125;
+    let sl_id = client.place_order(&owner, &stop_limit);
+
+    // No oracle price yet
+    let result = client.try_execute_order(&keeper, &tp_id);
+    assert_eq!(result, Err(Ok(TradeError::OracleUnavailable)));
+
+    // A non-positive oracle price is no reference at all
+    oracle.set_price(&asset, &0, &1_000);
+    let result = client.try_execute_order(&keeper, &sl_id);
+    assert_eq!(result, Err(Ok(TradeError::NoReferencePrice)));
+
+    oracle.set_price(&asset, &112, &1_000);
+    client.execute_order(&keeper, &tp_id);
+    assert_eq!(client.get_position(&owner, &pair).base_qty, -10);
+
+    // Triggered, but the market is already past the limit
+    oracle.set_price(&asset, &130, &1_000);
+    let result = client.try_execute_order(&keeper, &sl_id);
+    assert_eq!(result, Err(Ok(TradeError::LimitPriceNotMet)));
+
+    oracle.set_price(&asset, &122, &1_000);
+    client.execute_order(&keeper, &sl_id);
+
+    let position = client.get_position(&owner, &pair);
+    assert_eq!(position.base_qty, 0);
+    assert_eq!(position.realized_pnl, 10 * (112 - 125));
+
+    // Stale oracle prices never trigger orders
+    let mut stale = order_request(&env, &pair, OrderKind::TakeProfit, false, 110, &token_id);
+    stale.trigger_source = TriggerSource::Oracle;
+    let stale_id = client.place_order(&owner, &stale);
+    env.ledger().with_mut(|li| li.timestamp = 1_500);
+    let result = client.try_execute_order(&keeper, &stale_id);
+    assert_eq!(result, Err(Ok(TradeError::StalePrice)));
+}
+
+#[test]
+fn test_order_cancel_and_expiry_refund_escrow() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let owner = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    token_admin.mint(&owner, &100);
+
+    let request = order_request(&env, &pair, OrderKind::StopMarket, false, 90, &token_id);
+
+    // Invalid parameters are rejected
+    let mut bad = request.clone();
+    bad.expiry = 1_000;
+    assert_eq!(client.try_place_order(&owner, &bad), Err(Ok(TradeError::InvalidOrder)));
+    let mut bad = request.clone();
+    bad.kind = OrderKind::StopLimit;
+    assert_eq!(client.try_place_order(&owner, &bad), Err(Ok(TradeError::InvalidOrder)));
+    let mut bad = request.clone();
+    bad.fee.amount = i128::MAX;
+    assert_eq!(client.try_place_order(&owner, &bad), Err(Ok(TradeError::InvalidOrder)));
+
+    // Only the owner can cancel
+    let cancelled = client.place_order(&owner, &request);
+    let result = client.try_cancel_order(&keeper, &cancelled);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+    client.cancel_order(&owner, &cancelled);
+    assert_eq!(client.get_order(&cancelled).unwrap().status, OrderStatus::Cancelled);
+    assert_eq!(token_client.balance(&owner), 100);
+
+    let expiring = client.place_order(&owner, &request);
+    let result = client.try_expire_order(&expiring);
+    assert_eq!(result, Err(Ok(TradeError::OrderNotExpired)));
+
+    // Past expiry the order can no longer fill, only be expired
+    env.ledger().with_mut(|li| li.timestamp = 5_001);
+    client.trade(&owner, &pair, &1, &80, &true, &token_id, &0, &keeper, &None);
+    let result = client.try_execute_order(&keeper, &expiring);
+    assert_eq!(result, Err(Ok(TradeError::OrderExpired)));
+
+    client.expire_order(&expiring);
+    assert_eq!(client.get_order(&expiring).unwrap().status, OrderStatus::Expired);
+    assert_eq!(token_client.balance(&owner), 100);
+
+    let result = client.try_cancel_order(&owner, &expiring);
+    assert_eq!(result, Err(Ok(TradeError::OrderNotOpen)));
+    let result = client.try_execute_order(&keeper, &99);
+    assert_eq!(result, Err(Ok(TradeError::OrderNotFound)));
+}
+
+fn schedule_request(env: &Env, pair: &Symbol, token: &Address) -> ScheduleRequest {
+    ScheduleRequest {
+        pair: pair.clone(),
+        is_buy: true,
+        total_amount: 25,
+        slice_amount: 10,
+        interval: 3_600,
+        start_time: 1_000,
+        end_time: 10_000,
+        price_source: TriggerSource::LastTrade,
+        limit_price: 0,
+        fee: TradeFee {
+            token: token.clone(),
+            amount: 2,
+            recipient: Address::generate(env),
+        },
+        bounty: 1,
+    }
+}
+
+#[test]
+fn test_dca_schedule_executes_slices_on_interval() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let owner = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    token_admin.mint(&owner, &100);
+
+    client.trade(&keeper, &pair, &1, &100, &true, &token_id, &0, &keeper, &None);
+
+    let request = schedule_request(&env, &pair, &token_id);
+    let recipient = request.fee.recipient.clone();
+    let id = client.create_schedule(&owner, &request);
+
+    // Three slices, each escrowing a fee of 2 and a bounty of 1
+    assert_eq!(token_client.balance(&owner), 91);
+    assert_eq!(client.get_schedule(&id).unwrap().escrow, 9);
+
+    client.execute_slice(&keeper, &id);
+    let result = client.try_execute_slice(&keeper, &id);
+    assert_eq!(result, Err(Ok(TradeError::SliceNotDue)));
+
+    env.ledger().with_mut(|li| li.timestamp = 4_600);
+    client.trade(&keeper, &pair, &1, &120, &true, &token_id, &0, &keeper, &None);
+    client.execute_slice(&keeper, &id);
+
+    env.ledger().with_mut(|li| li.timestamp = 8_200);
+    let trade_id = client.execute_slice(&keeper, &id);
+
+    let schedule = client.get_schedule(&id).unwrap();
+    assert_eq!(schedule.status, ScheduleStatus::Completed);
+    assert_eq!(schedule.executed_amount, 25);
+    assert_eq!(schedule.slices_executed, 3);
+    assert_eq!(schedule.escrow, 0);
+    assert_eq!(client.get_stats().last_trade_id, trade_id);
+
+    // Slices of 10 at 100, then 10 and 5 at 120
+    let position = client.get_position(&owner, &pair);
+    assert_eq!(position.base_qty, 25);
+    assert_eq!(position.avg_entry_price, (10 * 100 + 15 * 120) / 25);
+
+    assert_eq!(token_client.balance(&keeper), 3);
+    assert_eq!(token_client.balance(&recipient), 6);
+
+    let result = client.try_execute_slice(&keeper, &id);
+    assert_eq!(result, Err(Ok(TradeError::ScheduleNotActive)));
+}
+
+#[test]
+fn test_dca_schedule_cancel_refunds_unspent_escrow() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let owner = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    token_admin.mint(&owner, &100);
+
+    // Slices cannot all fit before the end time
+    let mut bad = schedule_request(&env, &pair, &token_id);
+    bad.end_time = 5_000;
+    assert_eq!(client.try_create_schedule(&owner, &bad), Err(Ok(TradeError::InvalidSchedule)));
+
+    let mut request = schedule_request(&env, &pair, &token_id);
+    request.limit_price = 110;
+    let id = client.create_schedule(&owner, &request);
+
+    // No trade price for the pair yet
+    let result = client.try_execute_slice(&keeper, &id);
+    assert_eq!(result, Err(Ok(TradeError::NoReferencePrice)));
+
+    client.trade(&keeper, &pair, &1, &100, &true, &token_id, &0, &keeper, &None);
+    client.execute_slice(&keeper, &id);
+
+    // Price moved above the buy limit
+    env.ledger().with_mut(|li| li.timestamp = 4_600);
+    client.trade(&keeper, &pair, &1, &115, &true, &token_id, &0, &keeper, &None);
+    let result = client.try_execute_slice(&keeper, &id);
+    assert_eq!(result, Err(Ok(TradeError::LimitPriceNotMet)));
+
+    let result = client.try_cancel_schedule(&keeper, &id);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+
+    // Two slices were never executed
+    assert_eq!(client.cancel_schedule(&owner, &id), 6);
+    assert_eq!(token_client.balance(&owner), 97);
+    assert_eq!(client.get_schedule(&id).unwrap().status, ScheduleStatus::Cancelled);
+
+    let result = client.try_cancel_schedule(&owner, &id);
+    assert_eq!(result, Err(Ok(TradeError::ScheduleNotActive)));
+    let result = client.try_execute_slice(&keeper, &99);
+    assert_eq!(result, Err(Ok(TradeError::ScheduleNotFound)));
+}
+
+fn no_limits() -> TraderLimits {
+    TraderLimits {
+        max_trades_per_window: 0,
+        window_ledgers: 0,
+        max_daily_notional: 0,
+        max_open_orders: 0,
+    }
+}
+
+#[test]
+fn test_trade_rate_limited_per_ledger_window() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.sequence_number = 100);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _, _) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let bot = Address::generate(&env);
+    let other = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    let mut limits = no_limits();
+    limits.max_trades_per_window = 2;
+    let result = client.try_set_default_limits(&admin, &limits);
+    assert_eq!(result, Err(Ok(TradeError::InvalidLimitConfig)));
+
+    limits.window_ledgers = 10;
+    client.set_default_limits(&admin, &limits);
+
+    client.trade(&bot, &pair, &1, &100, &true, &token_id, &0, &recipient, &None);
+    client.trade(&bot, &pair, &1, &100, &true, &token_id, &0, &recipient, &None);
+    let result = client.try_trade(&bot, &pair, &1, &100, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::TradeRateLimited)));
+
+    // Limits are tracked per trader
+    client.trade(&other, &pair, &1, &100, &true, &token_id, &0, &recipient, &None);
+
+    // A new window resets the count
+    env.ledger().with_mut(|li| li.sequence_number = 110);
+    client.trade(&bot, &pair, &1, &100, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(client.get_trader_usage(&bot).window_trades, 1);
+
+    // An override replaces the default for one trader
+    client.set_trader_limits(&admin, &bot, &no_limits());
+    for _ in 0..3 {
+        client.trade(&bot, &pair, &1, &100, &true, &token_id, &0, &recipient, &None);
+    }
+    client.clear_trader_limits(&admin, &bot);
+    assert_eq!(client.get_trader_limits(&bot), limits);
+}
+
+#[test]
+fn test_daily_notional_and_open_order_limits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    token_admin.mint(&trader, &1_000);
+
+    let mut limits = no_limits();
+    limits.max_daily_notional = 5_000;
+    limits.max_open_orders = 2;
+    client.set_trader_limits(&admin, &trader, &limits);
+
+    client.trade(&trader, &pair, &30, &100, &true, &token_id, &0, &recipient, &None);
+    let result = client.try_trade(&trader, &pair, &30, &100, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::DailyNotionalExceeded)));
+    client.trade(&trader, &pair, &20, &100, &true, &token_id, &0, &recipient, &None);
+
+    // The next day starts a fresh allowance
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    client.trade(&trader, &pair, &30, &100, &true, &token_id, &0, &recipient, &None);
+
+    let mut order = order_request(&env, &pair, OrderKind::StopMarket, false, 90, &token_id);
+    order.expiry = 100_000;
+    let first = client.place_order(&trader, &order);
+
+    let mut schedule = schedule_request(&env, &pair, &token_id);
+    schedule.start_time = 86_400;
+    schedule.end_time = 100_000;
+    client.create_schedule(&trader, &schedule);
+
+    let result = client.try_place_order(&trader, &order);
+    assert_eq!(result, Err(Ok(TradeError::TooManyOpenOrders)));
+
+    // Closing an order frees a slot
+    client.cancel_order(&trader, &first);
+    assert_eq!(client.get_trader_usage(&trader).open_orders, 1);
+    client.place_order(&trader, &order);
+}
+
+#[test]
+fn test_restricted_pair_requires_valid_attestation() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, _, executor) = setup_governance(&env, 1);
+    let (token_id, _, _) = setup_fee_token(&env);
+    let regulated = symbol_short!("TBILLUSD");
+    let open = symbol_short!("XLMUSDC");
+
+    let officer = Address::generate(&env);
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    client.set_pair_restricted(&admin, &regulated, &true);
+    assert!(client.is_pair_restricted(&regulated));
+
+    let result = client.try_trade(&trader, &regulated, &1, &100, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::AttestationRequired)));
+
+    // Unrestricted pairs are unaffected
+    client.trade(&trader, &open, &1, &100, &true, &token_id, &0, &recipient, &None);
+
+    // Only the compliance role may attest
+    let kyc = symbol_short!("KYC42");
+    let result = client.try_attest(&officer, &trader, &kyc, &2_000);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+
+    // Granting the role waits out the role-change timelock like any other role
+    let change = client.set_compliance_officer(&admin, &officer);
+    let result = client.try_attest(&officer, &trader, &kyc, &2_000);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+    let now = 1_000 + ROLE_CHANGE_DELAY;
+    env.ledger().with_mut(|li| li.timestamp = now);
+    client.execute_role_change(&change, &executor);
+
+    let result = client.try_set_compliance_officer(&admin, &officer);
+    assert_eq!(result, Err(Ok(TradeError::RoleAlreadyAssigned)));
+    let result = client.try_attest(&officer, &trader, &kyc, &now);
+    assert_eq!(result, Err(Ok(TradeError::InvalidAttestation)));
+    client.attest(&officer, &trader, &kyc, &(now + 1_000));
+
+    client.trade(&trader, &regulated, &1, &100, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(client.get_attestation(&trader).unwrap().issuer, officer);
+
+    env.ledger().with_mut(|li| li.timestamp = now + 1_000);
+    let result = client.try_trade(&trader, &regulated, &1, &100, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::AttestationExpired)));
+
+    client.attest(&officer, &trader, &kyc, &(now + 2_000));
+    client.revoke_attestation(&officer, &trader);
+    let result = client.try_trade(&trader, &regulated, &1, &100, &true, &token_id, &0, &recipient, &None);
+    assert_eq!(result, Err(Ok(TradeError::AttestationRequired)));
+
+    // Removal is immediate
+    client.remove_compliance_officer(&admin, &officer);
+    let result = client.try_attest(&officer, &trader, &kyc, &(now + 2_000));
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+}
+
+#[test]
+fn test_trade_slippage_bounds_and_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, _, _) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+    let trader = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    // Buying 10 at 100 pays 1000 quote for 10 base
+    let bounds = TradeBounds { min_out: 10, max_in: 1_000, deadline: 1_000 };
+    let buy = Some(bounds.clone());
+    client.trade(&trader, &pair, &10, &100, &true, &token_id, &0, &recipient, &buy);
+
+    let result = client.try_trade(&trader, &pair, &10, &101, &true, &token_id, &0, &recipient, &buy);
+    assert_eq!(result, Err(Ok(TradeError::ExcessiveInput)));
+
+    // Selling 10 at 100 must receive at least 950 quote
+    let sell = Some(TradeBounds { min_out: 950, max_in: 0, deadline: 1_000 });
+    client.trade(&trader, &pair, &10, &95, &false, &token_id, &0, &recipient, &sell);
+    let result = client.try_trade(&trader, &pair, &10, &94, &false, &token_id, &0, &recipient, &sell);
+    assert_eq!(result, Err(Ok(TradeError::InsufficientOutput)));
+
+    // A signed trade can't execute once its deadline has passed
+    env.ledger().with_mut(|li| li.timestamp = 1_001);
+    let result = client.try_trade(&trader, &pair, &10, &100, &true, &token_id, &0, &recipient, &buy);
+    assert_eq!(result, Err(Ok(TradeError::DeadlineExpired)));
+
+    assert_eq!(client.get_stats().total_trades, 2);
+}
+
+fn trade_request(pair: &Symbol, amount: i128, fee: &TradeFee) -> TradeRequest {
+    TradeRequest {
+        pair: pair.clone(),
+        amount,
+        price: 100,
+        is_buy: true,
+        fee: fee.clone(),
+        bounds: TradeBounds::unbounded(),
+    }
+}
+
+#[test]
+fn test_trade_batch_best_effort_and_all_or_nothing() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let maker = Address::generate(&env);
+    token_admin.mint(&maker, &25);
+
+    let fee = TradeFee { token: token_id.clone(), amount: 10, recipient: Address::generate(&env) };
+    let mut limits = no_limits();
+    limits.max_daily_notional = 10_000;
+    client.set_trader_limits(&admin, &maker, &limits);
+
+    let requests = soroban_sdk::vec![
+        &env,
+        trade_request(&pair, 10, &fee),
+        trade_request(&pair, 0, &fee),
+        trade_request(&pair, 10, &fee),
+        trade_request(&pair, 10, &fee), // Fee balance runs out
+    ];
+
+    let outcomes = client.trade_batch(&maker, &requests, &BatchMode::BestEffort);
+    assert_eq!(
+        outcomes,
+        soroban_sdk::vec![
+            &env,
+            BatchOutcome::Ok(1),
+            BatchOutcome::Err(TradeError::InvalidAmount as u32),
+            BatchOutcome::Ok(2),
+            BatchOutcome::Err(TradeError::InsufficientBalance as u32),
+        ]
+    );
+
+    // Failed items leave no trace
+    assert_eq!(client.get_stats().total_trades, 2);
+    assert_eq!(client.get_trader_usage(&maker).day_notional, 2_000);
+    assert_eq!(token_client.balance(&maker), 5);
+
+    // All-or-nothing reverts the whole batch on the first failure
+    let fee = TradeFee { token: token_id.clone(), amount: 0, recipient: fee.recipient.clone() };
+    let requests = soroban_sdk::vec![
+        &env,
+        trade_request(&pair, 10, &fee),
+        trade_request(&pair, 100, &fee), // Over the daily notional
+    ];
+    let result = client.try_trade_batch(&maker, &requests, &BatchMode::AllOrNothing);
+    assert_eq!(result, Err(Ok(TradeError::DailyNotionalExceeded)));
+    assert_eq!(client.get_stats().total_trades, 2);
+
+    let empty: soroban_sdk::Vec<TradeRequest> = soroban_sdk::vec![&env];
+    let result = client.try_trade_batch(&maker, &empty, &BatchMode::BestEffort);
+    assert_eq!(result, Err(Ok(TradeError::InvalidBatch)));
+}
+
+#[test]
+fn test_cancel_batch_reports_per_order_results() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, _) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    let pair = symbol_short!("XLMUSDC");
+
+    let maker = Address::generate(&env);
+    let other = Address::generate(&env);
+    token_admin.mint(&maker, &100);
+    token_admin.mint(&other, &100);
+
+    let request = order_request(&env, &pair, OrderKind::StopMarket, false, 90, &token_id);
+    let first = client.place_order(&maker, &request);
+    let second = client.place_order(&maker, &request);
+    let foreign = client.place_order(&other, &request);
+
+    let ids = soroban_sdk::vec![&env, first, foreign, second, 99];
+    let result = client.try_cancel_batch(&maker, &ids, &BatchMode::AllOrNothing);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+    assert_eq!(client.get_order(&first).unwrap().status, OrderStatus::Open);
+
+    let outcomes = client.cancel_batch(&maker, &ids, &BatchMode::BestEffort);
+    assert_eq!(
+        outcomes,
+        soroban_sdk::vec![
+            &env,
+            BatchOutcome::Ok(first),
+            BatchOutcome::Err(TradeError::Unauthorized as u32),
+            BatchOutcome::Ok(second),
+            BatchOutcome::Err(TradeError::OrderNotFound as u32),
+        ]
+    );
+
+    assert_eq!(token_client.balance(&maker), 100);
+    assert_eq!(client.get_order(&foreign).unwrap().status, OrderStatus::Open);
+}
+
+#[test]
+fn test_migrate_v1_trades_into_per_id_keys() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, _, executor) = setup_governance(&env, 1);
+    let contract_id = client.address.clone();
+
+    // Recreate the v1 layout with every trade in a single Vec
+    let trader = Address::generate(&env);
+    let legacy = Trade {
+        id: 1,
+        trader: trader.clone(),
+        pair: symbol_short!("XLMUSDC"),
+        amount: 10,
+        price: 100,
+        timestamp: 0,
+        is_buy: true,
+    };
+    env.as_contract(&contract_id, || {
+        let trades = soroban_sdk::vec![&env, legacy.clone()];
+        env.storage().persistent().set(&symbol_short!("trades"), &trades);
+        env.storage().persistent().set(&symbol_short!("ver"), &1u32);
+    });
+
+    // Only the executor may migrate, and never to an older layout
+    let result = client.try_migrate(&admin, &1);
+    assert_eq!(result, Err(Ok(MigrationError::Unauthorized)));
+    let result = client.try_migrate(&executor, &(CONTRACT_VERSION + 1));
+    assert_eq!(result, Err(Ok(MigrationError::DowngradeNotAllowed)));
+
+    assert_eq!(client.migrate(&executor, &1), CONTRACT_VERSION);
+    assert_eq!(client.get_version(), CONTRACT_VERSION);
+    assert_eq!(client.get_trade(&1), Some(legacy));
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().persistent().has(&symbol_short!("trades")));
+    });
+
+    // Running the same migration twice is rejected
+    let result = client.try_migrate(&executor, &1);
+    assert_eq!(result, Err(Ok(MigrationError::InvalidMigration)));
+}
+
+#[test]
+fn test_execute_upgrade_installs_wasm() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approvers, executor) = setup_governance(&env, 1);
+    let approver = approvers.get(0).unwrap();
+    let contract_id = client.address.clone();
+
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+    let v2 = env.deployer().upload_contract_wasm(WASM_V2);
+
+    let proposal_id =
+        client.propose_upgrade(&admin, &v1, &symbol_short!("Upgrade"), &approvers, &1, &3_600);
+    client.approve_upgrade(&proposal_id, &approver);
+    env.ledger().with_mut(|li| li.timestamp = 3_600);
+    client.execute_upgrade(&proposal_id, &executor);
+
+    let event = env.events().all().last().unwrap();
+    assert_eq!(event.0, contract_id);
+    let (old, new): (Option<BytesN<32>>, BytesN<32>) = event.2.into_val(&env);
+    assert_eq!((old, new), (None, v1.clone()));
+
+    // Storage survives the upgrade with the new hash; only `migrate` moves
+    // the version on
+    env.as_contract(&contract_id, || {
+        assert_eq!(GovernanceManager::get_wasm_hash(&env), Some(v1));
+        assert_eq!(migrations::get_version(&env), CONTRACT_VERSION);
+    });
+
+    // The contract now runs the v1 code, which can move itself on to v2
+    let version = Symbol::new(&env, "version");
+    let installed: u32 = env.invoke_contract(&contract_id, &version, soroban_sdk::vec![&env]);
+    assert_eq!(installed, 1);
+
+    let args = soroban_sdk::vec![&env, v2.into_val(&env)];
+    env.invoke_contract::<()>(&contract_id, &Symbol::new(&env, "upgrade"), args);
+    let installed: u32 = env.invoke_contract(&contract_id, &version, soroban_sdk::vec![&env]);
+    assert_eq!(installed, 2);
+}
+
+#[test]
+fn test_governor_role_upgrades_directly() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, _, executor) = setup_governance(&env, 1);
+    let contract_id = client.address.clone();
+
+    let v1 = env.deployer().upload_contract_wasm(WASM_V1);
+    let governor = Address::generate(&env);
+
+    // Neither an unknown address nor a plain admin may upgrade directly
+    let result = client.try_upgrade(&governor, &v1);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+    let result = client.try_upgrade(&admin, &v1);
+    assert_eq!(result, Err(Ok(TradeError::Unauthorized)));
+
+    let change = client.grant_role(&admin, &GovernanceRole::Governor, &governor);
+    env.ledger().with_mut(|li| li.timestamp = 1_000 + ROLE_CHANGE_DELAY);
+    client.execute_role_change(&change, &executor);
+
+    client.upgrade(&governor, &v1);
+    env.as_contract(&contract_id, || {
+        assert_eq!(GovernanceManager::get_wasm_hash(&env), Some(v1));
+        assert_eq!(migrations::get_version(&env), CONTRACT_VERSION);
+    });
+
+    let version = Symbol::new(&env, "version");
+    let installed: u32 = env.invoke_contract(&contract_id, &version, soroban_sdk::vec![&env]);
+    assert_eq!(installed, 1);
+}
+
+#[test]
+fn test_action_proposal_runs_calls_after_approval() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, approvers, executor) = setup_governance(&env, 1);
+    let approver = approvers.get(0).unwrap();
+
+    let oracle_id = env.register_contract(None, MockOracle);
+    let oracle = MockOracleClient::new(&env, &oracle_id);
+    let asset = Asset::Other(symbol_short!("XLMUSDC"));
+
+    let set_price = ProposalAction {
+        target: oracle_id.clone(),
+        function: Symbol::new(&env, "set_price"),
+        args: soroban_sdk::vec![
+            &env,
+            asset.clone().into_val(&env),
+            1_234i128.into_val(&env),
+            1_000u64.into_val(&env),
+        ],
+    };
+    let decimals = ProposalAction {
+        target: oracle_id.clone(),
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let actions = soroban_sdk::vec![&env, set_price, decimals];
+
+    let description = symbol_short!("SetPrice");
+    let id = client.propose_actions(&admin, &actions, &description, &approvers, &1, &3_600);
+    client.approve_actions(&id, &approver);
+
+    // Still in timelock
+    let result = client.try_execute_actions(&id, &executor);
+    assert_eq!(result, Err(Ok(GovernanceError::TimelockNotExpired)));
+    assert_eq!(oracle.lastprice(&asset), None);
+
+    env.ledger().with_mut(|li| li.timestamp = 4_600);
+    let results = client.execute_actions(&id, &executor);
+    assert_eq!(results.len(), 2);
+    let decimals: u32 = results.get(1).unwrap().into_val(&env);
+    assert_eq!(decimals, 7);
+    assert_eq!(oracle.lastprice(&asset).unwrap().price, 1_234);
+
+    let proposal = client.get_action_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Executed);
+    assert!(client.try_execute_actions(&id, &executor).is_err());
+}
+
+#[test]
+fn test_action_proposal_failed_call_reverts_execution() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approvers, executor) = setup_governance(&env, 1);
+    let approver = approvers.get(0).unwrap();
+
+    let oracle_id = env.register_contract(None, MockOracle);
+    let missing = ProposalAction {
+        target: oracle_id,
+        function: symbol_short!("nope"),
+        args: soroban_sdk::vec![&env],
+    };
+    let actions = soroban_sdk::vec![&env, missing];
+
+    // Proposals need at least one action
+    let empty: soroban_sdk::Vec<ProposalAction> = soroban_sdk::vec![&env];
+    let description = symbol_short!("Broken");
+    assert!(client.try_propose_actions(&admin, &empty, &description, &approvers, &1, &3_600).is_err());
+
+    let id = client.propose_actions(&admin, &actions, &description, &approvers, &1, &3_600);
+    client.approve_actions(&id, &approver);
+    env.ledger().with_mut(|li| li.timestamp = 3_600);
+
+    let result = client.try_execute_actions(&id, &executor);
+    assert_eq!(result, Err(Ok(GovernanceError::ActionFailed)));
+    assert_eq!(client.get_action_proposal(&id).status, ProposalStatus::Approved);
+}
+
+fn voting_config(token: &Address, allow_vote_change: bool) -> VotingConfig {
+    VotingConfig {
+        token: token.clone(),
+        voting_delay: 100,
+        voting_period: 1_000,
+        quorum_bps: 4_000,
+        pass_threshold_bps: 5_000,
+        proposal_threshold: 10,
+        timelock_delay: 500,
+        allow_vote_change,
+    }
+}
+
+#[test]
+fn test_token_vote_lifecycle_uses_snapshot_weights() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    client.set_voting_config(&admin, &voting_config(&token_id, true));
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    token_admin.mint(&alice, &60);
+    token_admin.mint(&bob, &30);
+    token_admin.mint(&carol, &10);
+    client.register_voter(&alice);
+    client.register_voter(&bob);
+    client.register_voter(&carol);
+
+    let oracle_id = env.register_contract(None, MockOracle);
+    let oracle = MockOracleClient::new(&env, &oracle_id);
+    let asset = Asset::Other(symbol_short!("XLMUSDC"));
+    let set_price = ProposalAction {
+        target: oracle_id.clone(),
+        function: Symbol::new(&env, "set_price"),
+        args: soroban_sdk::vec![
+            &env,
+            asset.clone().into_val(&env),
+            1_234i128.into_val(&env),
+            1_000u64.into_val(&env),
+        ],
+    };
+    let actions = soroban_sdk::vec![&env, set_price];
+
+    let id = client.propose_vote(&alice, &actions, &symbol_short!("SetPrice"));
+    assert_eq!(client.get_vote_state(&id), VotingState::Pending);
+    assert_eq!(client.get_vote_proposal(&id).total_weight, 100);
+    assert_eq!(
+        client.try_cast_vote(&bob, &id, &VoteType::For),
+        Err(Ok(GovernanceError::VotingClosed))
+    );
+
+    // Later config changes don't apply to an existing proposal
+    let mut stricter = voting_config(&token_id, false);
+    stricter.quorum_bps = 10_000;
+    stricter.timelock_delay = 5_000;
+    client.set_voting_config(&admin, &stricter);
+    assert_eq!(client.get_vote_proposal(&id).config, voting_config(&token_id, true));
+
+    // Tokens moved after the snapshot don't change voting power
+    token_client.transfer(&carol, &alice, &10);
+    env.ledger().with_mut(|li| li.timestamp = 1_100);
+    assert_eq!(client.get_vote_state(&id), VotingState::Active);
+    assert_eq!(client.cast_vote(&alice, &id, &VoteType::Against), 60);
+    assert_eq!(client.cast_vote(&alice, &id, &VoteType::For), 60);
+    client.cast_vote(&bob, &id, &VoteType::Against);
+    assert_eq!(
+        client.try_cast_vote(&carol, &id, &VoteType::Abstain),
+        Ok(Ok(10))
+    );
+
+    let proposal = client.get_vote_proposal(&id);
+    assert_eq!((proposal.for_votes, proposal.against_votes, proposal.abstain_votes), (60, 30, 10));
+    assert_eq!(
+        client.get_vote_receipt(&id, &alice),
+        Some(VoteReceipt { support: VoteType::For, weight: 60 })
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 2_101);
+    assert_eq!(client.get_vote_state(&id), VotingState::Succeeded);
+    assert_eq!(
+        client.try_execute_vote(&id),
+        Err(Ok(GovernanceError::InvalidVotingState))
+    );
+
+    assert_eq!(client.queue_vote(&id), 2_601);
+    assert_eq!(client.get_vote_state(&id), VotingState::Queued);
+    assert_eq!(
+        client.try_execute_vote(&id),
+        Err(Ok(GovernanceError::TimelockNotExpired))
+    );
+    assert_eq!(oracle.lastprice(&asset), None);
+
+    env.ledger().with_mut(|li| li.timestamp = 2_601);
+    client.execute_vote(&id);
+    assert_eq!(oracle.lastprice(&asset).unwrap().price, 1_234);
+    assert_eq!(client.get_vote_state(&id), VotingState::Executed);
+}
+
+#[test]
+fn test_queued_vote_expires_after_grace_period() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _token_client, token_admin) = setup_fee_token(&env);
+    client.set_voting_config(&admin, &voting_config(&token_id, false));
+    let config = GovernanceConfig {
+        execution_grace_period: 500,
+        ..GovernanceConfig::default_config()
+    };
+    client.set_governance_config(&admin, &config);
+
+    let alice = Address::generate(&env);
+    token_admin.mint(&alice, &50);
+    client.register_voter(&alice);
+    let action = ProposalAction {
+        target: env.register_contract(None, MockOracle),
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let id = client.propose_vote(&alice, &soroban_sdk::vec![&env, action], &symbol_short!("Stale"));
+    assert_eq!(client.get_vote_proposal(&id).execution_grace_period, 500);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_100);
+    client.cast_vote(&alice, &id, &VoteType::For);
+    env.ledger().with_mut(|li| li.timestamp = 2_101);
+    assert_eq!(client.queue_vote(&id), 2_601);
+
+    env.ledger().with_mut(|li| li.timestamp = 3_101);
+    assert_eq!(client.get_vote_state(&id), VotingState::Queued);
+    env.ledger().with_mut(|li| li.timestamp = 3_102);
+    assert_eq!(client.get_vote_state(&id), VotingState::Expired);
+    assert_eq!(
+        client.try_execute_vote(&id),
+        Err(Ok(GovernanceError::InvalidVotingState))
+    );
+}
+
+#[test]
+fn test_token_voter_registration_is_capped() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, token_client, token_admin) = setup_fee_token(&env);
+    client.set_voting_config(&admin, &voting_config(&token_id, false));
+
+    // Fill every slot with dust
+    let mut voters = Vec::new(&env);
+    for _ in 0..MAX_VOTERS {
+        let voter = Address::generate(&env);
+        token_admin.mint(&voter, &1);
+        client.register_voter(&voter);
+        voters.push_back(voter);
+    }
+
+    let dust = Address::generate(&env);
+    token_admin.mint(&dust, &1);
+    assert_eq!(
+        client.try_register_voter(&dust),
+        Err(Ok(GovernanceError::TooManyVoters))
+    );
+
+    // A larger holder takes the smallest slot; an emptied balance goes first
+    let emptied = voters.get(3).unwrap();
+    token_client.transfer(&emptied, &dust, &1);
+    let holder = Address::generate(&env);
+    token_admin.mint(&holder, &50);
+    client.register_voter(&holder);
+    let registered = client.get_voters();
+    assert_eq!(registered.len(), MAX_VOTERS);
+    assert!(registered.contains(&holder));
+    assert!(!registered.contains(&emptied));
+
+    // Leaving frees a slot; only registered voters can leave
+    let leaving = voters.get(0).unwrap();
+    client.unregister_voter(&leaving);
+    assert_eq!(
+        client.try_unregister_voter(&leaving),
+        Err(Ok(GovernanceError::NoVotingPower))
+    );
+    client.register_voter(&dust);
+
+    let action = ProposalAction {
+        target: env.register_contract(None, MockOracle),
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let id = client.propose_vote(&holder, &soroban_sdk::vec![&env, action], &symbol_short!("Full"));
+    // 18 dust voters, `dust` now holding 2 and the holder's 50
+    assert_eq!(client.get_vote_proposal(&id).total_weight, 18 + 2 + 50);
+}
+
+#[test]
+fn test_migrate_v6_vote_proposals_snapshot_config() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, _, executor) = setup_governance(&env, 1);
+    let (token_id, _token_client, token_admin) = setup_fee_token(&env);
+    client.set_voting_config(&admin, &voting_config(&token_id, false));
+
+    let alice = Address::generate(&env);
+    token_admin.mint(&alice, &50);
+    client.register_voter(&alice);
+    let action = ProposalAction {
+        target: env.register_contract(None, MockOracle),
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let id = client.propose_vote(&alice, &soroban_sdk::vec![&env, action], &symbol_short!("Legacy"));
+    let proposal = client.get_vote_proposal(&id);
+
+    // Recreate the v6 layout, written before proposals held their config.
+    // The tuple encodes the same as `VotingKey::Proposal(id)`.
+    let legacy = VotingProposalV6 {
+        id,
+        proposer: proposal.proposer.clone(),
+        actions: proposal.actions.clone(),
+        description: proposal.description.clone(),
+        start_time: proposal.start_time,
+        end_time: proposal.end_time,
+        total_weight: proposal.total_weight,
+        for_votes: 0,
+        against_votes: 0,
+        abstain_votes: 0,
+        eta: 0,
+        queued: false,
+        executed: false,
+        cancelled: false,
+    };
+    env.as_contract(&client.address, || {
+        let storage = env.storage().persistent();
+        storage.set(&(symbol_short!("Proposal"), id), &legacy);
+        storage.set(&symbol_short!("ver"), &6u32);
+    });
+
+    assert_eq!(client.migrate(&executor, &6), CONTRACT_VERSION);
+    let migrated = client.get_vote_proposal(&id);
+    assert_eq!(migrated.config, voting_config(&token_id, false));
+    assert_eq!(
+        migrated.execution_grace_period,
+        client.get_governance_config().execution_grace_period
+    );
+    assert_eq!(migrated.total_weight, 50);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_100);
+    assert_eq!(client.cast_vote(&alice, &id, &VoteType::For), 50);
+}
+
+#[test]
+fn test_token_vote_quorum_and_voting_rules() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin) = setup_trading(&env);
+    let (token_id, _token_client, token_admin) = setup_fee_token(&env);
+
+    let mut bad = voting_config(&token_id, false);
+    bad.quorum_bps = 10_001;
+    assert_eq!(
+        client.try_set_voting_config(&admin, &bad),
+        Err(Ok(GovernanceError::InvalidVotingConfig))
+    );
+    let outsider = Address::generate(&env);
+    assert_eq!(
+        client.try_set_voting_config(&outsider, &voting_config(&token_id, false)),
+        Err(Ok(GovernanceError::Unauthorized))
+    );
+    client.set_voting_config(&admin, &voting_config(&token_id, false));
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let small = Address::generate(&env);
+    token_admin.mint(&alice, &30);
+    token_admin.mint(&bob, &70);
+    token_admin.mint(&small, &5);
+    client.register_voter(&alice);
+    client.register_voter(&bob);
+    assert_eq!(
+        client.try_register_voter(&outsider),
+        Err(Ok(GovernanceError::NoVotingPower))
+    );
+
+    let action = ProposalAction {
+        target: env.register_contract(None, MockOracle),
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let actions = soroban_sdk::vec![&env, action];
+    let description = symbol_short!("Quorum");
+
+    // Below the proposal threshold
+    assert_eq!(
+        client.try_propose_vote(&small, &actions, &description),
+        Err(Ok(GovernanceError::NoVotingPower))
+    );
+
+    let id = client.propose_vote(&alice, &actions, &description);
+    env.ledger().with_mut(|li| li.timestamp = 1_100);
+
+    // Unregistered holders have no snapshot weight
+    token_admin.mint(&outsider, &50);
+    assert_eq!(
+        client.try_cast_vote(&outsider, &id, &VoteType::For),
+        Err(Ok(GovernanceError::NoVotingPower))
+    );
+
+    client.cast_vote(&alice, &id, &VoteType::For);
+    assert_eq!(
+        client.try_cast_vote(&alice, &id, &VoteType::Against),
+        Err(Ok(GovernanceError::AlreadyVoted))
+    );
+
+    // 30 of 100 voted, below the 40% quorum
+    env.ledger().with_mut(|li| li.timestamp = 2_101);
+    assert_eq!(client.get_vote_state(&id), VotingState::Defeated);
+    assert_eq!(
+        client.try_queue_vote(&id),
+        Err(Ok(GovernanceError::InvalidVotingState))
+    );
+    assert_eq!(
+        client.try_cast_vote(&bob, &id, &VoteType::For),
+        Err(Ok(GovernanceError::VotingClosed))
+    );
+
+    // Admin can cancel a proposal that hasn't executed
+    let id = client.propose_vote(&alice, &actions, &description);
+    client.cancel_vote(&admin, &id);
+    assert_eq!(client.get_vote_state(&id), VotingState::Cancelled);
+    assert!(client.try_cancel_vote(&admin, &id).is_err());
+}
+
+#[test]
+fn test_role_changes_wait_for_timelock() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, approvers, executor) = setup_governance(&env, 1);
+    let approver = approvers.get(0).unwrap();
+
+    let newcomer = Address::generate(&env);
+    assert_eq!(
+        client.try_grant_role(&newcomer, &GovernanceRole::Approver, &newcomer),
+        Err(Ok(GovernanceError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_grant_role(&admin, &GovernanceRole::Approver, &approver),
+        Err(Ok(GovernanceError::RoleAlreadyHeld))
+    );
+
+    let id = client.grant_role(&admin, &GovernanceRole::Approver, &newcomer);
+    let change = client.get_role_change(&id);
+    assert_eq!(change.execution_time, 1_000 + ROLE_CHANGE_DELAY);
+    assert_eq!(
+        client.try_execute_role_change(&id, &executor),
+        Err(Ok(GovernanceError::TimelockNotExpired))
+    );
+    assert!(!client.has_role(&newcomer, &GovernanceRole::Approver));
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000 + ROLE_CHANGE_DELAY);
+    client.execute_role_change(&id, &executor);
+    assert_eq!(
+        client.get_role_members(&GovernanceRole::Approver),
+        soroban_sdk::vec![&env, approver.clone(), newcomer.clone()]
+    );
+    assert!(client.try_execute_role_change(&id, &executor).is_err());
+
+    // Addresses can hold several roles at once
+    let id = client.grant_role(&admin, &GovernanceRole::Admin, &newcomer);
+    env.ledger().with_mut(|li| li.timestamp = 1_000 + 2 * ROLE_CHANGE_DELAY);
+    client.execute_role_change(&id, &executor);
+    assert!(client.has_role(&newcomer, &GovernanceRole::Admin));
+    assert!(client.has_role(&newcomer, &GovernanceRole::Approver));
+
+    // Cancelled changes never apply
+    let id = client.revoke_role(&newcomer, &GovernanceRole::Approver, &approver);
+    client.cancel_role_change(&id, &admin);
+    env.ledger().with_mut(|li| li.timestamp = 1_000 + 3 * ROLE_CHANGE_DELAY);
+    assert!(client.try_execute_role_change(&id, &executor).is_err());
+    assert!(client.has_role(&approver, &GovernanceRole::Approver));
+}
+
+#[test]
+fn test_renounce_role_keeps_last_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, _, executor) = setup_governance(&env, 1);
+
+    assert_eq!(
+        client.try_renounce_role(&admin, &GovernanceRole::Admin),
+        Err(Ok(GovernanceError::LastAdmin))
+    );
+    assert_eq!(
+        client.try_renounce_role(&admin, &GovernanceRole::Approver),
+        Err(Ok(GovernanceError::RoleNotHeld))
+    );
+
+    let change = client.set_compliance_officer(&admin, &admin);
+    env.ledger().with_mut(|li| li.timestamp = ROLE_CHANGE_DELAY);
+    client.execute_role_change(&change, &executor);
+    client.renounce_role(&admin, &GovernanceRole::Compliance);
+    assert!(!client.has_role(&admin, &GovernanceRole::Compliance));
+    assert!(client.has_role(&admin, &GovernanceRole::Admin));
+}
+
+#[test]
+fn test_migrate_v2_roles_into_role_sets() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, UpgradeableTradingContract);
+    let client = UpgradeableTradingContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let approver = Address::generate(&env);
+    let executor = Address::generate(&env);
+
+    // Recreate the v2 layout with a single role per address
+    env.as_contract(&contract_id, || {
+        let mut roles = soroban_sdk::Map::new(&env);
+        roles.set(admin.clone(), GovernanceRole::Admin);
+        roles.set(approver.clone(), GovernanceRole::Approver);
+        roles.set(executor.clone(), GovernanceRole::Executor);
+        env.storage().persistent().set(&symbol_short!("roles"), &roles);
+        env.storage().persistent().set(&symbol_short!("ver"), &2u32);
+    });
+
+    // Legacy roles still authorize until the migration runs
+    assert!(client.has_role(&admin, &GovernanceRole::Admin));
+    assert!(client.get_role_members(&GovernanceRole::Admin).is_empty());
+    assert_eq!(client.migrate(&executor, &2), CONTRACT_VERSION);
+
+    assert_eq!(
+        client.get_role_members(&GovernanceRole::Admin),
+        soroban_sdk::vec![&env, admin.clone()]
+    );
+    assert!(client.has_role(&approver, &GovernanceRole::Approver));
+    assert!(client.has_role(&executor, &GovernanceRole::Executor));
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().persistent().has(&symbol_short!("roles")));
+    });
+}
+
+#[test]
+fn test_require_role_denies_unknown_and_uses_configured_hierarchy() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approvers, _) = setup_governance(&env, 1);
+    let approver = approvers.get(0).unwrap();
+
+    let oracle_id = env.register_contract(None, MockOracle);
+    let action = ProposalAction {
+        target: oracle_id,
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let actions = soroban_sdk::vec![&env, action];
+    let description = symbol_short!("Decimals");
+    let id = client.propose_actions(&admin, &actions, &description, &approvers, &1, &3_600);
+    client.approve_actions(&id, &approver);
+    env.ledger().with_mut(|li| li.timestamp = 3_600);
+
+    // Strangers used to pass Executor checks by default
+    let stranger = Address::generate(&env);
+    let result = client.try_execute_actions(&id, &stranger);
+    assert_eq!(result, Err(Ok(GovernanceError::Unauthorized)));
+
+    // Admin no longer implies lower roles unless configured
+    let result = client.try_execute_actions(&id, &admin);
+    assert_eq!(result, Err(Ok(GovernanceError::Unauthorized)));
+
+    let implied = soroban_sdk::vec![&env, GovernanceRole::Executor];
+    assert_eq!(
+        client.try_set_role_hierarchy(&stranger, &GovernanceRole::Admin, &implied),
+        Err(Ok(GovernanceError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_role_hierarchy(
+            &admin,
+            &GovernanceRole::Approver,
+            &soroban_sdk::vec![&env, GovernanceRole::Admin]
+        ),
+        Err(Ok(GovernanceError::InvalidRoleHierarchy))
+    );
+    assert_eq!(
+        client.try_set_role_hierarchy(
+            &admin,
+            &GovernanceRole::Admin,
+            &soroban_sdk::vec![&env, GovernanceRole::Governor]
+        ),
+        Err(Ok(GovernanceError::InvalidRoleHierarchy))
+    );
+
+    client.set_role_hierarchy(&admin, &GovernanceRole::Admin, &implied);
+    assert_eq!(client.get_role_hierarchy(&GovernanceRole::Admin), implied);
+    client.execute_actions(&id, &admin);
+    assert_eq!(client.get_action_proposal(&id).status, ProposalStatus::Executed);
+}
+
+#[test]
+fn test_proposals_expire_after_deadline_and_grace_period() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, approvers, executor) = setup_governance(&env, 1);
+    let approver = approvers.get(0).unwrap();
+
+    let bad = GovernanceConfig { voting_period: 0, ..GovernanceConfig::default_config() };
+    assert_eq!(
+        client.try_set_governance_config(&admin, &bad),
+        Err(Ok(GovernanceError::InvalidGovernanceConfig))
+    );
+    let config = GovernanceConfig {
+        voting_period: 1_000,
+        execution_grace_period: 500,
+        ..GovernanceConfig::default_config()
+    };
+    client.set_governance_config(&admin, &config);
+
+    let oracle_id = env.register_contract(None, MockOracle);
+    let action = ProposalAction {
+        target: oracle_id,
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let actions = soroban_sdk::vec![&env, action];
+    let description = symbol_short!("Stale");
+
+    // Never approved before the deadline
+    let unapproved = client.propose_actions(&admin, &actions, &description, &approvers, &1, &3_600);
+    // Approved but never executed within the grace period
+    let unexecuted = client.propose_actions(&admin, &actions, &description, &approvers, &1, &3_600);
+    client.approve_actions(&unexecuted, &approver);
+
+    let proposal = client.get_action_proposal(&unapproved);
+    assert_eq!(proposal.voting_deadline, 2_000);
+    assert_eq!(proposal.execution_grace_period, 500);
+    assert_eq!(client.get_expirable_proposals(&0, &10).len(), 0);
+    assert_eq!(
+        client.try_expire_proposal(&unexecuted),
+        Err(Ok(GovernanceError::ProposalNotExpired))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 2_001);
+    let result = client.try_approve_actions(&unapproved, &approver);
+    assert_eq!(result, Err(Ok(GovernanceError::ProposalExpired)));
+    assert_eq!(client.get_expirable_proposals(&0, &10), soroban_sdk::vec![&env, unapproved]);
+
+    env.ledger().with_mut(|li| li.timestamp = 5_101);
+    assert_eq!(client.get_expirable_proposals(&0, &10), soroban_sdk::vec![&env, unapproved, unexecuted]);
+    // Pages are addressed by proposal ID, so a sweep can resume mid-range
+    assert_eq!(client.get_proposal_count(), unexecuted);
+    assert_eq!(client.get_expirable_proposals(&unapproved, &10), soroban_sdk::vec![&env, unexecuted]);
+    assert_eq!(client.get_expirable_proposals(&0, &1), soroban_sdk::vec![&env, unapproved]);
+
+    // Execution is refused once the grace period has passed
+    let result = client.try_execute_actions(&unexecuted, &executor);
+    assert_eq!(result, Err(Ok(GovernanceError::ProposalExpired)));
+
+    client.expire_proposal(&unapproved);
+    client.expire_proposal(&unexecuted);
+    assert_eq!(client.get_action_proposal(&unapproved).status, ProposalStatus::Expired);
+    assert_eq!(client.get_action_proposal(&unexecuted).status, ProposalStatus::Expired);
+    assert_eq!(client.get_expirable_proposals(&0, &10).len(), 0);
+    assert!(client.try_expire_proposal(&99).is_err());
+}
+
+#[test]
+fn test_list_proposals_paginates_by_status() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approvers, _) = setup_governance(&env, 1);
+    let approver = approvers.get(0).unwrap();
+    // Role changes count separately and leave no gaps in proposal IDs
+    let change_id = client.grant_role(&admin, &GovernanceRole::Guardian, &Address::generate(&env));
+
+    let description = symbol_short!("Upgrade");
+    for i in 0..5u8 {
+        let hash = BytesN::from_array(&env, &[i; 32]);
+        client.propose_upgrade(&admin, &hash, &description, &approvers, &1, &3_600);
+    }
+    // Action proposals share the ID sequence but are listed separately
+    let action = ProposalAction {
+        target: env.register_contract(None, MockOracle),
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let actions = soroban_sdk::vec![&env, action];
+    let action_id = client.propose_actions(&admin, &actions, &description, &approvers, &1, &3_600);
+    client.approve_upgrade(&2, &approver);
+    client.approve_upgrade(&4, &approver);
+
+    assert_eq!(change_id, 1);
+    assert_eq!(action_id, 6);
+
+    let page = client.list_proposals(&None, &0, &3);
+    assert!(page.iter().map(|p| p.id).eq([1, 2, 3]));
+    let page = client.list_proposals(&None, &3, &10);
+    assert!(page.iter().map(|p| p.id).eq([4, 5]));
+    assert_eq!(client.list_proposals(&None, &5, &10).len(), 0);
+    // Pages are windows of proposal IDs, like `get_expirable_proposals`
+    let page = client.list_proposals(&None, &4, &2);
+    assert!(page.iter().map(|p| p.id).eq([5]));
+    assert_eq!(client.list_action_proposals(&None, &0, &5).len(), 0);
+
+    // The filter applies within the addressed page
+    let approved = client.list_proposals(&Some(ProposalStatus::Approved), &0, &10);
+    assert!(approved.iter().map(|p| p.id).eq([2, 4]));
+    let approved = client.list_proposals(&Some(ProposalStatus::Approved), &2, &10);
+    assert!(approved.iter().map(|p| p.id).eq([4]));
+    let approved = client.list_proposals(&Some(ProposalStatus::Approved), &0, &1);
+    assert_eq!(approved.len(), 0);
+
+    let actions = client.list_action_proposals(&Some(ProposalStatus::Pending), &0, &10);
+    assert!(actions.iter().map(|p| p.id).eq([action_id]));
+}
+
+#[test]
+fn test_migrate_v4_proposal_maps_into_per_id_keys() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approvers, executor) = setup_governance(&env, 2);
+    let approver = approvers.get(0).unwrap();
+    let contract_id = client.address.clone();
+
+    let hash = BytesN::from_array(&env, &[7; 32]);
+    let description = symbol_short!("Upgrade");
+    let id = client.propose_upgrade(&admin, &hash, &description, &approvers, &2, &3_600);
+    let mut proposal = client.get_upgrade_proposal(&id);
+    proposal.description = symbol_short!("Legacy");
+
+    // Recreate the v4 layout with every proposal and approval in one map
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().persistent();
+        let mut proposals = soroban_sdk::Map::new(&env);
+        proposals.set(id, proposal.clone());
+        storage.set(&symbol_short!("props"), &proposals);
+        let mut approvals = soroban_sdk::Map::new(&env);
+        approvals.set((id, approver.clone()), true);
+        storage.set(&symbol_short!("apprv"), &approvals);
+        storage.set(&symbol_short!("ver"), &4u32);
+    });
+
+    assert_eq!(client.migrate(&executor, &4), CONTRACT_VERSION);
+    assert_eq!(client.get_upgrade_proposal(&id), proposal);
+    assert_eq!(client.list_proposals(&None, &0, &10), soroban_sdk::vec![&env, proposal.clone()]);
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().persistent().has(&symbol_short!("props")));
+        assert!(!env.storage().persistent().has(&symbol_short!("apprv")));
+    });
+
+    // The migrated approval still blocks a duplicate
+    let result = client.try_approve_upgrade(&id, &approver);
+    assert_eq!(result, Err(Ok(GovernanceError::DuplicateApproval)));
+}
+
+#[test]
+fn test_migrate_v3_proposals_gain_deadlines() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, approvers, executor) = setup_governance(&env, 1);
+    let contract_id = client.address.clone();
+    let config = client.get_governance_config();
+
+    // Recreate the v3 layout, written before proposals had deadlines. The
+    // original layout named the new code by Symbol; later v3 code stored
+    // real WASM hashes.
+    let released = UpgradeProposalV1 {
+        id: 3,
+        proposer: admin.clone(),
+        new_contract_hash: symbol_short!("v2hash"),
+        target_contract: contract_id.clone(),
+        description: symbol_short!("Upgrade"),
+        approval_threshold: 1,
+        approvers: approvers.clone(),
+        approvals_count: 1,
+        status: ProposalStatus::Approved,
+        created_at: 400,
+        execution_time: 4_000,
+        executed: false,
+    };
+    let upgrade = UpgradeProposalV3 {
+        id: 1,
+        proposer: admin.clone(),
+        new_contract_hash: BytesN::from_array(&env, &[7; 32]),
+        target_contract: contract_id.clone(),
+        description: symbol_short!("Upgrade"),
+        approval_threshold: 1,
+        approvers: approvers.clone(),
+        approvals_count: 0,
+        status: ProposalStatus::Pending,
+        created_at: 500,
+        execution_time: 4_100,
+        executed: false,
+    };
+    let action = ProposalV3 {
+        id: 2,
+        proposer: admin.clone(),
+        actions: soroban_sdk::vec![
+            &env,
+            ProposalAction {
+                target: env.register_contract(None, MockOracle),
+                function: symbol_short!("decimals"),
+                args: soroban_sdk::vec![&env],
+            },
+        ],
+        description: symbol_short!("Decimals"),
+        approval_threshold: 1,
+        approvers: approvers.clone(),
+        approvals_count: 1,
+        status: ProposalStatus::Approved,
+        created_at: 600,
+        execution_time: 4_200,
+        executed: false,
+    };
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().persistent();
+        let mut upgrades: soroban_sdk::Map<u64, Val> = soroban_sdk::Map::new(&env);
+        upgrades.set(1, upgrade.into_val(&env));
+        upgrades.set(3, released.into_val(&env));
+        storage.set(&symbol_short!("props"), &upgrades);
+        let mut actions = soroban_sdk::Map::new(&env);
+        actions.set(2u64, action);
+        storage.set(&symbol_short!("act_props"), &actions);
+        storage.set(&symbol_short!("prop_cnt"), &3u64);
+        storage.set(&symbol_short!("ver"), &3u32);
+    });
+
+    assert_eq!(client.migrate(&executor, &3), CONTRACT_VERSION);
+
+    let upgrade = client.get_upgrade_proposal(&1);
+    assert_eq!(upgrade.voting_deadline, 500 + config.voting_period);
+    assert_eq!(upgrade.execution_grace_period, config.execution_grace_period);
+    assert_eq!(upgrade.new_contract_hash, BytesN::from_array(&env, &[7; 32]));
+
+    // A Symbol can never be installed, so the open proposal is cancelled
+    let released = client.get_upgrade_proposal(&3);
+    assert_eq!(released.status, ProposalStatus::Cancelled);
+    assert_eq!(released.new_contract_hash, BytesN::from_array(&env, &[0; 32]));
+    assert_eq!(released.voting_deadline, 400 + config.voting_period);
+
+    let action = client.get_action_proposal(&2);
+    assert_eq!(action.voting_deadline, 600 + config.voting_period);
+    assert_eq!(action.status, ProposalStatus::Approved);
+    assert!(client.list_proposals(&None, &0, &10).iter().map(|p| p.id).eq([1, 3]));
+    assert!(client.list_action_proposals(&None, &0, &10).iter().map(|p| p.id).eq([2]));
+
+    // Role changes shared the proposal counter, so theirs starts past it
+    let change_id = client.grant_role(&admin, &GovernanceRole::Guardian, &Address::generate(&env));
+    assert_eq!(change_id, 4);
+
+    // Migrated proposals run under the current rules
+    env.ledger().with_mut(|li| li.timestamp = 4_200);
+    client.execute_actions(&2, &executor);
+    assert_eq!(client.get_action_proposal(&2).status, ProposalStatus::Executed);
+}
+
+#[test]
+fn test_revoke_approval_returns_proposal_to_pending() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approvers, _) = setup_governance(&env, 2);
+    let approver1 = approvers.get(0).unwrap();
+    let approver2 = approvers.get(1).unwrap();
+
+    let hash = BytesN::from_array(&env, &[3; 32]);
+    let description = symbol_short!("Upgrade");
+    let id = client.propose_upgrade(&admin, &hash, &description, &approvers, &2, &3_600);
+    client.approve_upgrade(&id, &approver1);
+    client.approve_upgrade(&id, &approver2);
+    assert_eq!(client.get_upgrade_proposal(&id).status, ProposalStatus::Approved);
+
+    client.revoke_approval(&id, &approver2);
+    let proposal = client.get_upgrade_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Pending);
+    assert_eq!(proposal.approvals_count, 1);
+    assert_eq!(
+        client.try_revoke_approval(&id, &approver2),
+        Err(Ok(GovernanceError::ApprovalNotFound))
+    );
+
+    // A revoked approver can approve again
+    client.approve_upgrade(&id, &approver2);
+    assert_eq!(client.get_upgrade_proposal(&id).status, ProposalStatus::Approved);
+
+    // Once voting closes an approval can no longer be withdrawn
+    let deadline = client.get_upgrade_proposal(&id).voting_deadline;
+    env.ledger().with_mut(|li| li.timestamp = deadline + 1);
+    assert_eq!(
+        client.try_revoke_approval(&id, &approver1),
+        Err(Ok(GovernanceError::RevokeWindowClosed))
+    );
+    let proposal = client.get_upgrade_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Approved);
+    assert_eq!(proposal.approvals_count, 2);
+
+    // Approvals on finished proposals can't be withdrawn
+    client.cancel_upgrade(&id, &admin);
+    assert_eq!(
+        client.try_revoke_approval(&id, &approver1),
+        Err(Ok(GovernanceError::InvalidProposal))
+    );
+}
+
+#[test]
+fn test_guardian_vetoes_during_timelock() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (client, admin, approvers, executor) = setup_governance(&env, 1);
+    let approver = approvers.get(0).unwrap();
+
+    let guardian = Address::generate(&env);
+    let change = client.grant_role(&admin, &GovernanceRole::Guardian, &guardian);
+    env.ledger().with_mut(|li| li.timestamp = 1_000 + ROLE_CHANGE_DELAY);
+    client.execute_role_change(&change, &executor);
+
+    let action = ProposalAction {
+        target: env.register_contract(None, MockOracle),
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let actions = soroban_sdk::vec![&env, action];
+    let description = symbol_short!("Veto");
+    let vetoed = client.propose_actions(&admin, &actions, &description, &approvers, &1, &3_600);
+    let survivor = client.propose_actions(&admin, &actions, &description, &approvers, &1, &3_600);
+    client.approve_actions(&vetoed, &approver);
+    client.approve_actions(&survivor, &approver);
+
+    assert_eq!(
+        client.try_veto_proposal(&vetoed, &approver),
+        Err(Ok(GovernanceError::Unauthorized))
+    );
+    client.veto_proposal(&vetoed, &guardian);
+    assert_eq!(client.get_action_proposal(&vetoed).status, ProposalStatus::Vetoed);
+    let (_, topics, _) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("veto"), vetoed).into_val(&env));
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000 + ROLE_CHANGE_DELAY + 3_600);
+    let result = client.try_execute_actions(&vetoed, &executor);
+    assert_eq!(result, Err(Ok(GovernanceError::ProposalNotApproved)));
+
+    // Once the timelock is over the guardian can no longer step in
+    assert_eq!(
+        client.try_veto_proposal(&survivor, &guardian),
+        Err(Ok(GovernanceError::VetoWindowClosed))
+    );
+    client.execute_actions(&survivor, &executor);
+}
+
+#[test]
+fn test_timelock_bounds_per_action_type() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, admin, approvers, _) = setup_governance(&env, 3);
+
+    let hash = BytesN::from_array(&env, &[4; 32]);
+    let description = symbol_short!("Bounds");
+    let action = ProposalAction {
+        target: env.register_contract(None, MockOracle),
+        function: symbol_short!("decimals"),
+        args: soroban_sdk::vec![&env],
+    };
+    let actions = soroban_sdk::vec![&env, action];
+
+    // Defaults: 1 hour to 30 days for upgrades and parameter changes
+    assert_eq!(
+        client.try_propose_upgrade(&admin, &hash, &description, &approvers, &2, &0),
+        Err(Ok(GovernanceError::TimelockOutOfRange))
+    );
+    assert_eq!(
+        client.try_propose_actions(&admin, &actions, &description, &approvers, &2, &(31 * 86_400)),
+        Err(Ok(GovernanceError::TimelockOutOfRange))
+    );
+    client.propose_upgrade(&admin, &hash, &description, &approvers, &2, &3_600);
+
+    // Emergency actions may skip the delay but need two thirds of approvers
+    assert_eq!(
+        client.try_propose_emergency_actions(&admin, &actions, &description, &approvers, &1, &0),
+        Err(Ok(GovernanceError::InvalidThreshold))
+    );
+    assert_eq!(
+        client.try_propose_emergency_actions(&admin, &actions, &description, &approvers, &2, &7_200),
+        Err(Ok(GovernanceError::TimelockOutOfRange))
+    );
+    let id = client.propose_emergency_actions(&admin, &actions, &description, &approvers, &2, &0);
+    assert_eq!(client.get_action_proposal(&id).approval_threshold, 2);
+
+    // Bounds are configurable per action type
+    let mut config = GovernanceConfig::default_config();
+    config.upgrade_timelock = TimelockBounds { min_delay: 86_400, max_delay: 7 * 86_400 };
+    client.set_governance_config(&admin, &config);
+    assert_eq!(
+        client.try_propose_upgrade(&admin, &hash, &description, &approvers, &2, &3_600),
+        Err(Ok(GovernanceError::TimelockOutOfRange))
+    );
+    client.propose_upgrade(&admin, &hash, &description, &approvers, &2, &86_400);
+
+    config.parameter_timelock = TimelockBounds { min_delay: 10, max_delay: 5 };
+    assert_eq!(
+        client.try_set_governance_config(&admin, &config),
+        Err(Ok(GovernanceError::InvalidGovernanceConfig))
+    );
 }
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracterror, Address, Env, token};
+use soroban_sdk::{contracterror, contracttype, Address, Env, token};
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -6,6 +6,29 @@ use soroban_sdk::{contracterror, Address, Env, token};
 pub enum FeeError {
     InsufficientBalance = 1001,
     InvalidAmount = 1002,
+    InvalidShare = 1003,
+    NothingToWithdraw = 1004,
+}
+
+/// Denominator for fee shares expressed in basis points (10000 = 100%)
+pub const BPS_DENOMINATOR: u32 = 10_000;
+
+/// How collected fees reach their recipients
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum FeeMode {
+    Direct = 0,   // Transfer to the recipient on every charge
+    Accrue = 1,   // Hold in the contract until the recipient withdraws
+}
+
+/// Storage keys used by the fee manager in the host contract
+#[contracttype]
+#[derive(Clone)]
+pub enum FeeDataKey {
+    Mode,
+    Accrued(Address, Address), // (recipient, token) -> withdrawable balance
+    TotalAccrued(Address),     // token -> balance held for all recipients
 }
 
 pub struct FeeManager;
@@ -51,4 +74,172 @@ impl FeeManager {
 
         Ok(())
     }
+
+    /// Charges a fee according to the configured `FeeMode`.
+    ///
+    /// In `Direct` mode this is `collect_fee`; in `Accrue` mode the fee is
+    /// pulled into the contract and credited to the destination.
+    pub fn charge_fee(
+        env: &Env,
+        token: &Address,
+        payer: &Address,
+        destination: &Address,
+        amount: i128,
+    ) -> Result<(), FeeError> {
+        match Self::get_mode(env) {
+            FeeMode::Direct => Self::collect_fee(env, token, payer, destination, amount),
+            FeeMode::Accrue => Self::accrue_fee(env, token, payer, destination, amount),
+        }
+    }
+
+    /// Pulls a fee from a payer into the contract and credits it to a recipient.
+    ///
+    /// # Arguments
+    /// * `env` - The environment
+    /// * `token` - The token contract address to pay fees in
+    /// * `payer` - The address paying the fee
+    /// * `recipient` - The address the fee is credited to
+    /// * `amount` - The amount of fee to pay
+    ///
+    /// # Returns
+    /// * `Result<(), FeeError>` - Ok if successful, Error otherwise
+    pub fn accrue_fee(
+        env: &Env,
+        token: &Address,
+        payer: &Address,
+        recipient: &Address,
+        amount: i128,
+    ) -> Result<(), FeeError> {
+        let contract = env.current_contract_address();
+        Self::collect_fee(env, token, payer, &contract, amount)?;
+
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accrued_key = FeeDataKey::Accrued(recipient.clone(), token.clone());
+        let accrued = Self::accrued_fees(env, recipient, token);
+        env.storage().persistent().set(&accrued_key, &(accrued + amount));
+
+        let total_key = FeeDataKey::TotalAccrued(token.clone());
+        let total = Self::total_accrued(env, token);
+        env.storage().persistent().set(&total_key, &(total + amount));
+
+        Ok(())
+    }
+
+    /// Pays out everything accrued to a recipient in a token.
+    ///
+    /// The caller is responsible for authorizing `recipient`.
+    ///
+    /// # Returns
+    /// * `Result<i128, FeeError>` - The amount withdrawn
+    pub fn withdraw_fees(
+        env: &Env,
+        token: &Address,
+        recipient: &Address,
+    ) -> Result<i128, FeeError> {
+        let amount = Self::accrued_fees(env, recipient, token);
+        if amount == 0 {
+            return Err(FeeError::NothingToWithdraw);
+        }
+
+        // Clear the balance before transferring out
+        env.storage()
+            .persistent()
+            .remove(&FeeDataKey::Accrued(recipient.clone(), token.clone()));
+
+        let total_key = FeeDataKey::TotalAccrued(token.clone());
+        let total = Self::total_accrued(env, token);
+        env.storage().persistent().set(&total_key, &(total - amount));
+
+        let token_client = token::Client::new(env, token);
+        token_client.transfer(&env.current_contract_address(), recipient, &amount);
+
+        Ok(amount)
+    }
+
+    /// Fees accrued to a recipient in a token and not yet withdrawn
+    pub fn accrued_fees(env: &Env, recipient: &Address, token: &Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&FeeDataKey::Accrued(recipient.clone(), token.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Total fees held by the contract in a token for all recipients
+    pub fn total_accrued(env: &Env, token: &Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&FeeDataKey::TotalAccrued(token.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Set how fees are delivered to recipients
+    pub fn set_mode(env: &Env, mode: FeeMode) {
+        env.storage().persistent().set(&FeeDataKey::Mode, &mode);
+    }
+
+    /// Get how fees are delivered to recipients (defaults to `Direct`)
+    pub fn get_mode(env: &Env) -> FeeMode {
+        env.storage()
+            .persistent()
+            .get(&FeeDataKey::Mode)
+            .unwrap_or(FeeMode::Direct)
+    }
+
+    /// Collects a fee and routes a share of it to a referrer.
+    ///
+    /// The referrer receives `amount * referral_bps / 10000`, the destination
+    /// receives the remainder. Both legs are paid by `payer` and follow the
+    /// configured `FeeMode`.
+    ///
+    /// # Arguments
+    /// * `env` - The environment
+    /// * `token` - The token contract address to pay fees in
+    /// * `payer` - The address paying the fee
+    /// * `destination` - The address receiving the protocol part of the fee
+    /// * `referrer` - The address receiving the referral part of the fee
+    /// * `amount` - The total amount of fee to pay
+    /// * `referral_bps` - Referrer share in basis points
+    ///
+    /// # Returns
+    /// * `Result<i128, FeeError>` - The amount paid to the referrer
+    pub fn collect_fee_with_referral(
+        env: &Env,
+        token: &Address,
+        payer: &Address,
+        destination: &Address,
+        referrer: &Address,
+        amount: i128,
+        referral_bps: u32,
+    ) -> Result<i128, FeeError> {
+        if referral_bps > BPS_DENOMINATOR {
+            return Err(FeeError::InvalidShare);
+        }
+
+        if amount < 0 {
+            return Err(FeeError::InvalidAmount);
+        }
+
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        let referral_amount = amount
+            .checked_mul(referral_bps as i128)
+            .ok_or(FeeError::InvalidAmount)?
+            / BPS_DENOMINATOR as i128;
+
+        // Check the payer covers both legs before moving anything
+        let token_client = token::Client::new(env, token);
+        if token_client.balance(payer) < amount {
+            return Err(FeeError::InsufficientBalance);
+        }
+
+        Self::charge_fee(env, token, payer, referrer, referral_amount)?;
+        Self::charge_fee(env, token, payer, destination, amount - referral_amount)?;
+
+        Ok(referral_amount)
+    }
 }
@@ -1,4 +1,9 @@
-use soroban_sdk::{contracttype, Address, Env, Vec, Symbol, symbol_short};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, Env, IntoVal, TryFromVal, Val, Vec, Symbol, symbol_short};
+
+use crate::fees::BPS_DENOMINATOR;
+use crate::safe_call::safe_invoke;
+
+pub mod voting;
 
 /// Upgrade proposal that must be approved via governance
 #[contracttype]
@@ -6,7 +11,7 @@ use soroban_sdk::{contracttype, Address, Env, Vec, Symbol, symbol_short};
 pub struct UpgradeProposal {
     pub id: u64,
     pub proposer: Address,
-    pub new_contract_hash: Symbol,
+    pub new_contract_hash: BytesN<32>,     // Hash of WASM already uploaded to the network
     pub target_contract: Address,
     pub description: Symbol,
     pub approval_threshold: u32,           // e.g., 2 of 3
@@ -15,12 +20,99 @@ pub struct UpgradeProposal {
     pub status: ProposalStatus,
     pub created_at: u64,
     pub execution_time: u64,               // Timelock: when it can be executed
+    pub voting_deadline: u64,              // Approvals refused after this
+    pub execution_grace_period: u64,       // Seconds after execution_time before it expires
+    pub executed: bool,
+}
+
+/// Contract call run when a proposal executes
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProposalAction {
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+}
+
+/// General proposal that runs a list of contract calls once approved,
+/// e.g. fee changes, pair listings, pausing or badge creation
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Address,
+    pub actions: Vec<ProposalAction>,
+    pub description: Symbol,
+    pub approval_threshold: u32,
+    pub approvers: Vec<Address>,
+    pub approvals_count: u32,
+    pub status: ProposalStatus,
+    pub created_at: u64,
+    pub execution_time: u64,
+    pub voting_deadline: u64,
+    pub execution_grace_period: u64,
+    pub executed: bool,
+}
+
+/// Upgrade proposal in the original layout, which named the new code by
+/// `Symbol`; kept to decode old `"props"` maps during migration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradeProposalV1 {
+    pub id: u64,
+    pub proposer: Address,
+    pub new_contract_hash: Symbol,
+    pub target_contract: Address,
+    pub description: Symbol,
+    pub approval_threshold: u32,
+    pub approvers: Vec<Address>,
+    pub approvals_count: u32,
+    pub status: ProposalStatus,
+    pub created_at: u64,
+    pub execution_time: u64,
+    pub executed: bool,
+}
+
+/// Upgrade proposal as stored before voting deadlines and grace periods
+/// existed, kept to decode old `"props"` maps during migration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradeProposalV3 {
+    pub id: u64,
+    pub proposer: Address,
+    pub new_contract_hash: BytesN<32>,
+    pub target_contract: Address,
+    pub description: Symbol,
+    pub approval_threshold: u32,
+    pub approvers: Vec<Address>,
+    pub approvals_count: u32,
+    pub status: ProposalStatus,
+    pub created_at: u64,
+    pub execution_time: u64,
+    pub executed: bool,
+}
+
+/// Action proposal as stored before voting deadlines and grace periods
+/// existed, kept to decode old `"act_props"` maps during migration
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct ProposalV3 {
+    pub id: u64,
+    pub proposer: Address,
+    pub actions: Vec<ProposalAction>,
+    pub description: Symbol,
+    pub approval_threshold: u32,
+    pub approvers: Vec<Address>,
+    pub approvals_count: u32,
+    pub status: ProposalStatus,
+    pub created_at: u64,
+    pub execution_time: u64,
     pub executed: bool,
 }
 
 /// Status of an upgrade proposal
 #[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum ProposalStatus {
     Pending = 0,
@@ -28,19 +120,25 @@ pub enum ProposalStatus {
     Rejected = 2,
     Executed = 3,
     Cancelled = 4,
+    Expired = 5,     // Not approved by the deadline, or not executed within the grace period
+    Vetoed = 6,      // Blocked by a guardian during its timelock
 }
 
 /// Governance role
 #[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum GovernanceRole {
     Admin = 0,        // Can propose upgrades and cancel
     Approver = 1,     // Can approve/reject proposals
     Executor = 2,     // Can execute approved proposals (after timelock)
+    Compliance = 3,   // Can issue and revoke trader attestations
+    Guardian = 4,     // Can veto proposals during their timelock
+    Governor = 5,     // External governance contract that may upgrade directly
 }
 
 /// Governance error codes
+#[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum GovernanceError {
@@ -52,32 +150,411 @@ pub enum GovernanceError {
     InvalidThreshold = 2006,
     DuplicateApproval = 2007,
     ProposalNotFound = 2008,
+    ActionFailed = 2009,
+    InvalidVotingConfig = 2010,
+    VotingClosed = 2011,
+    AlreadyVoted = 2012,
+    NoVotingPower = 2013,
+    InvalidVotingState = 2014,
+    TooManyVoters = 2015,
+    RoleAlreadyHeld = 2016,
+    RoleNotHeld = 2017,
+    LastAdmin = 2018,
+    InvalidRoleHierarchy = 2019,
+    ProposalExpired = 2020,
+    ProposalNotExpired = 2021,
+    InvalidGovernanceConfig = 2022,
+    ApprovalNotFound = 2023,
+    VetoWindowClosed = 2024,
+    TimelockOutOfRange = 2025,
+    AlreadyInitialized = 2026,
+    RevokeWindowClosed = 2027,
+}
+
+/// Kind of change a proposal makes, which selects its timelock bounds
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum ActionType {
+    Upgrade = 0,          // WASM upgrades
+    ParameterChange = 1,  // Regular action proposals
+    Emergency = 2,        // Short-delay actions with a higher approval threshold
+}
+
+/// Allowed range for a proposal's timelock delay, in seconds
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimelockBounds {
+    pub min_delay: u64,
+    pub max_delay: u64,
+}
+
+/// Proposal lifetime settings, snapshotted into each proposal at creation
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GovernanceConfig {
+    pub voting_period: u64,            // Seconds after creation that approvals are accepted
+    pub execution_grace_period: u64,   // Seconds after the timelock that execution stays open
+    pub upgrade_timelock: TimelockBounds,
+    pub parameter_timelock: TimelockBounds,
+    pub emergency_timelock: TimelockBounds,
+    pub emergency_threshold_bps: u32,  // Minimum share of approvers an emergency proposal needs
+}
+
+impl GovernanceConfig {
+    pub fn default_config() -> Self {
+        GovernanceConfig {
+            voting_period: 7 * 86_400,
+            execution_grace_period: 14 * 86_400,
+            upgrade_timelock: TimelockBounds { min_delay: 3_600, max_delay: 30 * 86_400 },
+            parameter_timelock: TimelockBounds { min_delay: 3_600, max_delay: 30 * 86_400 },
+            emergency_timelock: TimelockBounds { min_delay: 0, max_delay: 3_600 },
+            emergency_threshold_bps: 6_600, // roughly two thirds
+        }
+    }
+
+    pub fn timelock_bounds(&self, action_type: ActionType) -> &TimelockBounds {
+        match action_type {
+            ActionType::Upgrade => &self.upgrade_timelock,
+            ActionType::ParameterChange => &self.parameter_timelock,
+            ActionType::Emergency => &self.emergency_timelock,
+        }
+    }
+}
+
+/// Proposal entries are extended to ~90 days whenever they fall below ~30 days
+pub const PROPOSAL_TTL_THRESHOLD: u32 = 30 * 17_280;
+pub const PROPOSAL_TTL_EXTEND: u32 = 90 * 17_280;
+
+/// Largest page returned by `list_proposals` and `get_expirable_proposals`
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// Delay between an admin scheduling a role change and it taking effect
+pub const ROLE_CHANGE_DELAY: u64 = 86_400;
+
+/// Timelocked grant or revocation of a role
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoleChange {
+    pub id: u64,
+    pub proposer: Address,
+    pub account: Address,
+    pub role: GovernanceRole,
+    pub grant: bool,                       // false = revoke
+    pub status: ProposalStatus,
+    pub created_at: u64,
+    pub execution_time: u64,
+}
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Proposal(u64),
+    ActionProposal(u64),
+    Approval(u64, Address),
+    RoleMembers(GovernanceRole),
+    RoleImplies(GovernanceRole),
+    RoleChange(u64),
+    TargetWasm(Address),
+    // Position in creation order -> proposal ID, one sequence per kind
+    ProposalIndex(u64),
+    ActionProposalIndex(u64),
 }
 
 pub struct GovernanceManager;
 
 impl GovernanceManager {
-    /// Validate that an address has a specific role
-    pub fn require_role(env: &Env, address: &Address, required_role: GovernanceRole) {
+    /// Require that `address` holds `required_role`, either directly or
+    /// through a role configured to imply it. Unknown addresses are denied.
+    pub fn require_role(
+        env: &Env,
+        address: &Address,
+        required_role: GovernanceRole,
+    ) -> Result<(), GovernanceError> {
+        for role in Self::get_roles(env, address).iter() {
+            if role == required_role || Self::get_implied_roles(env, role).contains(required_role) {
+                return Ok(());
+            }
+        }
+
+        Err(GovernanceError::Unauthorized)
+    }
+
+    /// Roles that holders of `role` are also treated as holding.
+    /// Empty by default, so every role must be granted explicitly.
+    pub fn get_implied_roles(env: &Env, role: GovernanceRole) -> Vec<GovernanceRole> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RoleImplies(role))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Configure which roles `role` implies (admin only). Implications are
+    /// not transitive and never extend to Admin or Governor, which must be
+    /// held explicitly.
+    pub fn set_implied_roles(
+        env: &Env,
+        admin: Address,
+        role: GovernanceRole,
+        implied: Vec<GovernanceRole>,
+    ) -> Result<(), GovernanceError> {
+        if !Self::has_role(env, &admin, GovernanceRole::Admin) {
+            return Err(GovernanceError::Unauthorized);
+        }
+
+        if implied.contains(GovernanceRole::Admin)
+            || implied.contains(GovernanceRole::Governor)
+            || implied.contains(role)
+        {
+            return Err(GovernanceError::InvalidRoleHierarchy);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::RoleImplies(role), &implied);
+
+        env.events()
+            .publish((symbol_short!("role_hier"), role), implied);
+
+        Ok(())
+    }
+
+    /// Whether `account` holds `role`
+    pub fn has_role(env: &Env, account: &Address, role: GovernanceRole) -> bool {
+        Self::get_role_members(env, role).contains(account)
+            || Self::legacy_role(env, account) == Some(role)
+    }
+
+    /// Every role held by `account`
+    pub fn get_roles(env: &Env, account: &Address) -> Vec<GovernanceRole> {
+        let mut roles = Vec::new(env);
+        for role in [
+            GovernanceRole::Admin,
+            GovernanceRole::Approver,
+            GovernanceRole::Executor,
+            GovernanceRole::Compliance,
+            GovernanceRole::Guardian,
+            GovernanceRole::Governor,
+        ] {
+            if Self::has_role(env, account, role) {
+                roles.push_back(role);
+            }
+        }
+        roles
+    }
+
+    /// Addresses holding `role`
+    pub fn get_role_members(env: &Env, role: GovernanceRole) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RoleMembers(role))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Role from the single-role `"roles"` map written before addresses
+    /// could hold several roles. Empty once the host contract has migrated.
+    fn legacy_role(env: &Env, account: &Address) -> Option<GovernanceRole> {
+        let roles: soroban_sdk::Map<Address, GovernanceRole> =
+            env.storage().persistent().get(&symbol_short!("roles"))?;
+        roles.get(account.clone())
+    }
+
+    /// Move the legacy `"roles"` map into per-role member lists
+    pub fn migrate_legacy_roles(env: &Env) {
         let roles_key = symbol_short!("roles");
-        let role_map: soroban_sdk::Map<Address, GovernanceRole> = env
-            .storage()
+        let roles: Option<soroban_sdk::Map<Address, GovernanceRole>> =
+            env.storage().persistent().get(&roles_key);
+
+        env.storage().persistent().remove(&roles_key);
+
+        for (account, role) in roles.into_iter().flatten() {
+            Self::assign_role(env, account, role);
+        }
+    }
+
+    /// Give `account` a role immediately. Meant for initialization and
+    /// migrations; runtime changes go through `grant_role`.
+    pub fn assign_role(env: &Env, account: Address, role: GovernanceRole) {
+        let mut members = Self::get_role_members(env, role);
+        if members.contains(&account) {
+            return;
+        }
+
+        members.push_back(account.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::RoleMembers(role), &members);
+
+        env.events()
+            .publish((symbol_short!("role_grnt"), role), account);
+    }
+
+    /// Take a role from `account` immediately, keeping at least one admin
+    pub fn remove_role(
+        env: &Env,
+        account: &Address,
+        role: GovernanceRole,
+    ) -> Result<(), GovernanceError> {
+        let mut members = Self::get_role_members(env, role);
+        let index = members
+            .first_index_of(account)
+            .ok_or(GovernanceError::RoleNotHeld)?;
+
+        if role == GovernanceRole::Admin && members.len() == 1 {
+            return Err(GovernanceError::LastAdmin);
+        }
+
+        members.remove(index);
+        env.storage()
+            .persistent()
+            .set(&DataKey::RoleMembers(role), &members);
+
+        env.events()
+            .publish((symbol_short!("role_rvk"), role), account.clone());
+
+        Ok(())
+    }
+
+    /// Schedule granting `role` to `account` after `ROLE_CHANGE_DELAY` (admin only)
+    pub fn grant_role(
+        env: &Env,
+        admin: Address,
+        role: GovernanceRole,
+        account: Address,
+    ) -> Result<u64, GovernanceError> {
+        if Self::has_role(env, &account, role) {
+            return Err(GovernanceError::RoleAlreadyHeld);
+        }
+
+        Self::schedule_role_change(env, admin, role, account, true)
+    }
+
+    /// Schedule revoking `role` from `account` after `ROLE_CHANGE_DELAY` (admin only)
+    pub fn revoke_role(
+        env: &Env,
+        admin: Address,
+        role: GovernanceRole,
+        account: Address,
+    ) -> Result<u64, GovernanceError> {
+        if !Self::has_role(env, &account, role) {
+            return Err(GovernanceError::RoleNotHeld);
+        }
+
+        Self::schedule_role_change(env, admin, role, account, false)
+    }
+
+    fn schedule_role_change(
+        env: &Env,
+        admin: Address,
+        role: GovernanceRole,
+        account: Address,
+        grant: bool,
+    ) -> Result<u64, GovernanceError> {
+        if !Self::has_role(env, &admin, GovernanceRole::Admin) {
+            return Err(GovernanceError::Unauthorized);
+        }
+
+        let id = Self::next_id(env, symbol_short!("role_cnt"));
+        let now = env.ledger().timestamp();
+        let change = RoleChange {
+            id,
+            proposer: admin,
+            account: account.clone(),
+            role,
+            grant,
+            status: ProposalStatus::Pending,
+            created_at: now,
+            execution_time: now + ROLE_CHANGE_DELAY,
+        };
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::RoleChange(id), &change);
+
+        env.events()
+            .publish((symbol_short!("role_prop"), id), (role, account, grant));
+
+        Ok(id)
+    }
+
+    /// Apply a scheduled role change once its timelock has passed
+    pub fn execute_role_change(
+        env: &Env,
+        change_id: u64,
+        executor: Address,
+    ) -> Result<(), GovernanceError> {
+        Self::require_role(env, &executor, GovernanceRole::Executor)?;
+
+        let mut change = Self::get_role_change(env, change_id)?;
+
+        if change.status != ProposalStatus::Pending {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        if env.ledger().timestamp() < change.execution_time {
+            return Err(GovernanceError::TimelockNotExpired);
+        }
+
+        if change.grant {
+            Self::assign_role(env, change.account.clone(), change.role);
+        } else {
+            Self::remove_role(env, &change.account, change.role)?;
+        }
+
+        change.status = ProposalStatus::Executed;
+        env.storage()
             .persistent()
-            .get(&roles_key)
-            .unwrap_or_else(|_| soroban_sdk::Map::new(env));
+            .set(&DataKey::RoleChange(change_id), &change);
+
+        Ok(())
+    }
+
+    /// Cancel a scheduled role change (admin only)
+    pub fn cancel_role_change(
+        env: &Env,
+        change_id: u64,
+        admin: Address,
+    ) -> Result<(), GovernanceError> {
+        if !Self::has_role(env, &admin, GovernanceRole::Admin) {
+            return Err(GovernanceError::Unauthorized);
+        }
+
+        let mut change = Self::get_role_change(env, change_id)?;
 
-        let user_role = role_map.get(address.clone()).unwrap_or(GovernanceRole::Executor);
-        
-        if user_role > required_role {
-            env.panic_with_error(symbol_short!("UNAUTH"));
+        if change.status != ProposalStatus::Pending {
+            return Err(GovernanceError::InvalidProposal);
         }
+
+        change.status = ProposalStatus::Cancelled;
+        env.storage()
+            .persistent()
+            .set(&DataKey::RoleChange(change_id), &change);
+
+        Ok(())
+    }
+
+    /// Give up one of your own roles immediately. The last admin can't renounce.
+    pub fn renounce_role(
+        env: &Env,
+        account: Address,
+        role: GovernanceRole,
+    ) -> Result<(), GovernanceError> {
+        Self::remove_role(env, &account, role)
+    }
+
+    pub fn get_role_change(env: &Env, change_id: u64) -> Result<RoleChange, GovernanceError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RoleChange(change_id))
+            .ok_or(GovernanceError::ProposalNotFound)
     }
 
     /// Create a new upgrade proposal
+    #[allow(clippy::too_many_arguments)]
     pub fn propose_upgrade(
         env: &Env,
         proposer: Address,
-        new_contract_hash: Symbol,
+        new_contract_hash: BytesN<32>,
         target_contract: Address,
         description: Symbol,
         approval_threshold: u32,
@@ -85,22 +562,19 @@ impl GovernanceManager {
         timelock_delay: u64,
     ) -> Result<u64, GovernanceError> {
         // Validate proposer is admin
-        Self::require_role(env, &proposer, GovernanceRole::Admin);
+        Self::require_role(env, &proposer, GovernanceRole::Admin)?;
 
         // Validate threshold
-        if approval_threshold == 0 || approval_threshold as usize > approvers.len() {
+        if approval_threshold == 0 || approval_threshold > approvers.len() {
             return Err(GovernanceError::InvalidThreshold);
         }
 
-        // Get next proposal ID
-        let proposal_counter_key = symbol_short!("prop_cnt");
-        let proposal_id: u64 = env
-            .storage()
-            .persistent()
-            .get(&proposal_counter_key)
-            .unwrap_or(0u64);
+        let config = Self::get_config(env);
+        Self::check_timelock(&config, ActionType::Upgrade, timelock_delay)?;
 
-        let next_id = proposal_id + 1;
+        // Get next proposal ID
+        let next_id = Self::next_id(env, symbol_short!("prop_cnt"));
+        let now = env.ledger().timestamp();
 
         let proposal = UpgradeProposal {
             id: next_id,
@@ -112,26 +586,16 @@ impl GovernanceManager {
             approvers,
             approvals_count: 0,
             status: ProposalStatus::Pending,
-            created_at: env.ledger().timestamp(),
-            execution_time: env.ledger().timestamp() + timelock_delay,
+            created_at: now,
+            execution_time: now + timelock_delay,
+            voting_deadline: now + config.voting_period,
+            execution_grace_period: config.execution_grace_period,
             executed: false,
         };
 
         // Store proposal
-        let proposals_key = symbol_short!("props");
-        let mut proposals: soroban_sdk::Map<u64, UpgradeProposal> = env
-            .storage()
-            .persistent()
-            .get(&proposals_key)
-            .unwrap_or_else(|_| soroban_sdk::Map::new(env));
-
-        proposals.set(next_id, proposal);
-        env.storage().persistent().set(&proposals_key, &proposals);
-
-        // Update counter
-        env.storage()
-            .persistent()
-            .set(&proposal_counter_key, &next_id);
+        Self::save_proposal(env, &proposal);
+        Self::append_index(env, symbol_short!("upg_cnt"), DataKey::ProposalIndex, next_id);
 
         Ok(next_id)
     }
@@ -143,44 +607,25 @@ impl GovernanceManager {
         approver: Address,
     ) -> Result<(), GovernanceError> {
         // Validate approver has permission
-        Self::require_role(env, &approver, GovernanceRole::Approver);
-
-        let proposals_key = symbol_short!("props");
-        let mut proposals: soroban_sdk::Map<u64, UpgradeProposal> = env
-            .storage()
-            .persistent()
-            .get(&proposals_key)
-            .ok_or(GovernanceError::ProposalNotFound)?;
+        Self::require_role(env, &approver, GovernanceRole::Approver)?;
 
-        let mut proposal = proposals
-            .get(proposal_id)
-            .ok_or(GovernanceError::ProposalNotFound)?;
+        let mut proposal = Self::get_proposal(env, proposal_id)?;
 
         // Validate proposal status
         if proposal.status != ProposalStatus::Pending {
             return Err(GovernanceError::InvalidProposal);
         }
 
-        // Validate approver is in the list
-        if !proposal.approvers.iter().any(|a| a == &approver) {
-            return Err(GovernanceError::Unauthorized);
+        if env.ledger().timestamp() > proposal.voting_deadline {
+            return Err(GovernanceError::ProposalExpired);
         }
 
-        // Check for duplicate approval
-        let approvals_key = symbol_short!("apprv");
-        let mut approvals: soroban_sdk::Map<(u64, Address), bool> = env
-            .storage()
-            .persistent()
-            .get(&approvals_key)
-            .unwrap_or_else(|_| soroban_sdk::Map::new(env));
-
-        if approvals.get((proposal_id, approver.clone())).is_some() {
-            return Err(GovernanceError::DuplicateApproval);
+        // Validate approver is in the list
+        if !proposal.approvers.contains(&approver) {
+            return Err(GovernanceError::Unauthorized);
         }
 
-        // Record approval
-        approvals.set((proposal_id, approver), true);
-        env.storage().persistent().set(&approvals_key, &approvals);
+        Self::record_approval(env, proposal_id, approver)?;
 
         // Increment approval count
         proposal.approvals_count += 1;
@@ -190,8 +635,7 @@ impl GovernanceManager {
             proposal.status = ProposalStatus::Approved;
         }
 
-        proposals.set(proposal_id, proposal);
-        env.storage().persistent().set(&proposals_key, &proposals);
+        Self::save_proposal(env, &proposal);
 
         Ok(())
     }
@@ -203,18 +647,9 @@ impl GovernanceManager {
         executor: Address,
     ) -> Result<(), GovernanceError> {
         // Validate executor has permission
-        Self::require_role(env, &executor, GovernanceRole::Executor);
+        Self::require_role(env, &executor, GovernanceRole::Executor)?;
 
-        let proposals_key = symbol_short!("props");
-        let mut proposals: soroban_sdk::Map<u64, UpgradeProposal> = env
-            .storage()
-            .persistent()
-            .get(&proposals_key)
-            .ok_or(GovernanceError::ProposalNotFound)?;
-
-        let mut proposal = proposals
-            .get(proposal_id)
-            .ok_or(GovernanceError::ProposalNotFound)?;
+        let mut proposal = Self::get_proposal(env, proposal_id)?;
 
         // Validate proposal is approved
         if proposal.status != ProposalStatus::Approved {
@@ -226,44 +661,97 @@ impl GovernanceManager {
             return Err(GovernanceError::TimelockNotExpired);
         }
 
+        if env.ledger().timestamp() > proposal.execution_time + proposal.execution_grace_period {
+            return Err(GovernanceError::ProposalExpired);
+        }
+
         // Mark as executed
         proposal.executed = true;
         proposal.status = ProposalStatus::Executed;
 
-        proposals.set(proposal_id, proposal);
-        env.storage().persistent().set(&proposals_key, &proposals);
+        Self::save_proposal(env, &proposal);
+
+        Self::install_wasm(env, &proposal.target_contract, &proposal.new_contract_hash);
 
         Ok(())
     }
 
-    /// Reject a proposal
-    pub fn reject_proposal(
-        env: &Env,
-        proposal_id: u64,
-        rejector: Address,
-    ) -> Result<(), GovernanceError> {
-        Self::require_role(env, &rejector, GovernanceRole::Approver);
+    /// Install a new WASM hash on the target contract and record it. Other
+    /// contracts install the hash through their own `upgrade(admin, hash)`
+    /// entrypoint, called with this contract as the admin. The new code takes
+    /// effect from the next invocation.
+    fn install_wasm(env: &Env, target: &Address, new_hash: &BytesN<32>) {
+        if *target == env.current_contract_address() {
+            Self::install_current_wasm(env, new_hash);
+            return;
+        }
 
-        let proposals_key = symbol_short!("props");
-        let mut proposals: soroban_sdk::Map<u64, UpgradeProposal> = env
-            .storage()
-            .persistent()
-            .get(&proposals_key)
-            .ok_or(GovernanceError::ProposalNotFound)?;
+        let key = DataKey::TargetWasm(target.clone());
+        let old_hash: Option<BytesN<32>> = env.storage().persistent().get(&key);
 
-        let mut proposal = proposals
-            .get(proposal_id)
-            .ok_or(GovernanceError::ProposalNotFound)?;
+        env.invoke_contract::<()>(
+            target,
+            &Symbol::new(env, "upgrade"),
+            soroban_sdk::vec![
+                env,
+                env.current_contract_address().into_val(env),
+                new_hash.into_val(env),
+            ],
+        );
+        env.storage().persistent().set(&key, new_hash);
 
-        if proposal.status != ProposalStatus::Pending {
-            return Err(GovernanceError::InvalidProposal);
-        }
+        env.events().publish(
+            (symbol_short!("upgrade"), target.clone()),
+            (old_hash, new_hash.clone()),
+        );
+    }
 
-        proposal.status = ProposalStatus::Rejected;
-        proposals.set(proposal_id, proposal);
-        env.storage().persistent().set(&proposals_key, &proposals);
+    /// Swap the current contract's code and record the hash. The stored
+    /// version is left for the new code's `migrate` to set. Callers are
+    /// responsible for authorizing the upgrade.
+    pub fn install_current_wasm(env: &Env, new_hash: &BytesN<32>) {
+        let wasm_key = symbol_short!("wasm");
+        let old_hash: Option<BytesN<32>> = env.storage().persistent().get(&wasm_key);
 
-        Ok(())
+        env.deployer().update_current_contract_wasm(new_hash.clone());
+        env.storage().persistent().set(&wasm_key, new_hash);
+
+        env.events().publish(
+            (symbol_short!("upgrade"), env.current_contract_address()),
+            (old_hash, new_hash.clone()),
+        );
+    }
+
+    /// Hash of the WASM this contract last installed on another contract
+    pub fn get_target_wasm_hash(env: &Env, target: &Address) -> Option<BytesN<32>> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TargetWasm(target.clone()))
+    }
+
+    /// Hash of the WASM installed by the last executed upgrade
+    pub fn get_wasm_hash(env: &Env) -> Option<BytesN<32>> {
+        env.storage().persistent().get(&symbol_short!("wasm"))
+    }
+
+    /// Reject a proposal
+    pub fn reject_proposal(
+        env: &Env,
+        proposal_id: u64,
+        rejector: Address,
+    ) -> Result<(), GovernanceError> {
+        Self::require_role(env, &rejector, GovernanceRole::Approver)?;
+
+        let mut proposal = Self::get_proposal(env, proposal_id)?;
+
+        if proposal.status != ProposalStatus::Pending {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        proposal.status = ProposalStatus::Rejected;
+        Self::save_proposal(env, &proposal);
+
+        Ok(())
     }
 
     /// Cancel a proposal (admin only)
@@ -272,44 +760,811 @@ impl GovernanceManager {
         proposal_id: u64,
         admin: Address,
     ) -> Result<(), GovernanceError> {
-        Self::require_role(env, &admin, GovernanceRole::Admin);
+        Self::require_role(env, &admin, GovernanceRole::Admin)?;
+
+        let mut proposal = Self::get_proposal(env, proposal_id)?;
+
+        if proposal.executed {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        proposal.status = ProposalStatus::Cancelled;
+        Self::save_proposal(env, &proposal);
+
+        Ok(())
+    }
+
+    /// Record an approver's vote, rejecting duplicates
+    fn record_approval(
+        env: &Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        let key = DataKey::Approval(proposal_id, approver);
+
+        if env.storage().persistent().has(&key) {
+            return Err(GovernanceError::DuplicateApproval);
+        }
+
+        env.storage().persistent().set(&key, &true);
+        Self::extend_ttl(env, &key);
+
+        Ok(())
+    }
+
+    /// Withdraw an approval from a proposal that hasn't executed. An
+    /// Approved proposal that drops below its threshold returns to Pending.
+    pub fn revoke_approval(
+        env: &Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        let key = DataKey::Approval(proposal_id, approver.clone());
+        if !env.storage().persistent().has(&key) {
+            return Err(GovernanceError::ApprovalNotFound);
+        }
+
+        if let Ok(mut proposal) = Self::get_action_proposal(env, proposal_id) {
+            proposal.status = Self::status_after_revoke(
+                env,
+                proposal.status,
+                &mut proposal.approvals_count,
+                proposal.approval_threshold,
+                proposal.voting_deadline,
+            )?;
+            Self::save_action_proposal(env, proposal);
+        } else {
+            let mut proposal = Self::get_proposal(env, proposal_id)?;
+            proposal.status = Self::status_after_revoke(
+                env,
+                proposal.status,
+                &mut proposal.approvals_count,
+                proposal.approval_threshold,
+                proposal.voting_deadline,
+            )?;
+            Self::save_proposal(env, &proposal);
+        }
+
+        env.storage().persistent().remove(&key);
+
+        env.events()
+            .publish((symbol_short!("appr_rev"), proposal_id), approver);
+
+        Ok(())
+    }
+
+    fn status_after_revoke(
+        env: &Env,
+        status: ProposalStatus,
+        approvals_count: &mut u32,
+        approval_threshold: u32,
+        voting_deadline: u64,
+    ) -> Result<ProposalStatus, GovernanceError> {
+        if status != ProposalStatus::Pending && status != ProposalStatus::Approved {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        // Approvals can't be replaced after the deadline, so a late revocation
+        // would strand an approved proposal short of its threshold
+        if env.ledger().timestamp() > voting_deadline {
+            return Err(GovernanceError::RevokeWindowClosed);
+        }
+
+        *approvals_count -= 1;
+        if *approvals_count < approval_threshold {
+            Ok(ProposalStatus::Pending)
+        } else {
+            Ok(status)
+        }
+    }
+
+    /// Block a pending or approved proposal before its timelock ends (guardian only)
+    pub fn veto_proposal(
+        env: &Env,
+        proposal_id: u64,
+        guardian: Address,
+    ) -> Result<(), GovernanceError> {
+        Self::require_role(env, &guardian, GovernanceRole::Guardian)?;
+
+        let now = env.ledger().timestamp();
+        if let Ok(mut proposal) = Self::get_action_proposal(env, proposal_id) {
+            Self::check_vetoable(proposal.status, proposal.execution_time, now)?;
+            proposal.status = ProposalStatus::Vetoed;
+            Self::save_action_proposal(env, proposal);
+        } else {
+            let mut proposal = Self::get_proposal(env, proposal_id)?;
+            Self::check_vetoable(proposal.status, proposal.execution_time, now)?;
+            proposal.status = ProposalStatus::Vetoed;
+            Self::save_proposal(env, &proposal);
+        }
+
+        env.events()
+            .publish((symbol_short!("veto"), proposal_id), guardian);
+
+        Ok(())
+    }
+
+    fn check_vetoable(
+        status: ProposalStatus,
+        execution_time: u64,
+        now: u64,
+    ) -> Result<(), GovernanceError> {
+        if status != ProposalStatus::Pending && status != ProposalStatus::Approved {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        if now >= execution_time {
+            return Err(GovernanceError::VetoWindowClosed);
+        }
+
+        Ok(())
+    }
 
-        let proposals_key = symbol_short!("props");
-        let mut proposals: soroban_sdk::Map<u64, UpgradeProposal> = env
-            .storage()
+    /// Keep proposal data alive well past any voting or grace period
+    fn extend_ttl(env: &Env, key: &DataKey) {
+        env.storage()
             .persistent()
-            .get(&proposals_key)
-            .ok_or(GovernanceError::ProposalNotFound)?;
+            .extend_ttl(key, PROPOSAL_TTL_THRESHOLD, PROPOSAL_TTL_EXTEND);
+    }
+
+    fn save_proposal(env: &Env, proposal: &UpgradeProposal) {
+        let key = DataKey::Proposal(proposal.id);
+        env.storage().persistent().set(&key, proposal);
+        Self::extend_ttl(env, &key);
+    }
+
+    /// Bump the counter at `counter_key` and return the new value. Upgrade
+    /// and action proposals share `"prop_cnt"` so a proposal ID names exactly
+    /// one proposal; role changes count separately under `"role_cnt"`.
+    fn next_id(env: &Env, counter_key: Symbol) -> u64 {
+        let next_id = Self::count(env, &counter_key) + 1;
+
+        env.storage().persistent().set(&counter_key, &next_id);
+
+        next_id
+    }
+
+    fn count(env: &Env, counter_key: &Symbol) -> u64 {
+        env.storage().persistent().get(counter_key).unwrap_or(0)
+    }
+
+    /// Record `id` as the next entry of a per-kind index so list pages can
+    /// be read by position
+    fn append_index(env: &Env, counter_key: Symbol, index_key: fn(u64) -> DataKey, id: u64) {
+        let position = Self::count(env, &counter_key);
+        let key = index_key(position);
+        env.storage().persistent().set(&key, &id);
+        Self::extend_ttl(env, &key);
+        env.storage().persistent().set(&counter_key, &(position + 1));
+    }
+
+    fn save_action_proposal(env: &Env, proposal: Proposal) {
+        let key = DataKey::ActionProposal(proposal.id);
+        env.storage().persistent().set(&key, &proposal);
+        Self::extend_ttl(env, &key);
+    }
+
+    /// Number of IDs handed out so far, across both proposal kinds
+    pub fn proposal_count(env: &Env) -> u64 {
+        Self::count(env, &symbol_short!("prop_cnt"))
+    }
+
+    /// Create a proposal that runs `actions` in order once approved and
+    /// past its timelock
+    pub fn propose_actions(
+        env: &Env,
+        proposer: Address,
+        actions: Vec<ProposalAction>,
+        description: Symbol,
+        approval_threshold: u32,
+        approvers: Vec<Address>,
+        timelock_delay: u64,
+    ) -> Result<u64, GovernanceError> {
+        Self::create_action_proposal(
+            env,
+            proposer,
+            actions,
+            description,
+            approval_threshold,
+            approvers,
+            timelock_delay,
+            ActionType::ParameterChange,
+        )
+    }
+
+    /// Create an action proposal on the emergency path: a shorter timelock
+    /// window, but at least `emergency_threshold_bps` of approvers must approve
+    pub fn propose_emergency_actions(
+        env: &Env,
+        proposer: Address,
+        actions: Vec<ProposalAction>,
+        description: Symbol,
+        approval_threshold: u32,
+        approvers: Vec<Address>,
+        timelock_delay: u64,
+    ) -> Result<u64, GovernanceError> {
+        let config = Self::get_config(env);
+        let required = approvers.len() as u64 * config.emergency_threshold_bps as u64;
+        if (approval_threshold as u64) * (BPS_DENOMINATOR as u64) < required {
+            return Err(GovernanceError::InvalidThreshold);
+        }
+
+        Self::create_action_proposal(
+            env,
+            proposer,
+            actions,
+            description,
+            approval_threshold,
+            approvers,
+            timelock_delay,
+            ActionType::Emergency,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_action_proposal(
+        env: &Env,
+        proposer: Address,
+        actions: Vec<ProposalAction>,
+        description: Symbol,
+        approval_threshold: u32,
+        approvers: Vec<Address>,
+        timelock_delay: u64,
+        action_type: ActionType,
+    ) -> Result<u64, GovernanceError> {
+        Self::require_role(env, &proposer, GovernanceRole::Admin)?;
 
-        let mut proposal = proposals
-            .get(proposal_id)
-            .ok_or(GovernanceError::ProposalNotFound)?;
+        if approval_threshold == 0 || approval_threshold > approvers.len() {
+            return Err(GovernanceError::InvalidThreshold);
+        }
+
+        if actions.is_empty() {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        let config = Self::get_config(env);
+        Self::check_timelock(&config, action_type, timelock_delay)?;
+
+        let id = Self::next_id(env, symbol_short!("prop_cnt"));
+        let now = env.ledger().timestamp();
+
+        Self::save_action_proposal(
+            env,
+            Proposal {
+                id,
+                proposer,
+                actions,
+                description,
+                approval_threshold,
+                approvers,
+                approvals_count: 0,
+                status: ProposalStatus::Pending,
+                created_at: now,
+                execution_time: now + timelock_delay,
+                voting_deadline: now + config.voting_period,
+                execution_grace_period: config.execution_grace_period,
+                executed: false,
+            },
+        );
+        Self::append_index(env, symbol_short!("act_cnt"), DataKey::ActionProposalIndex, id);
+
+        Ok(id)
+    }
+
+    fn check_timelock(
+        config: &GovernanceConfig,
+        action_type: ActionType,
+        timelock_delay: u64,
+    ) -> Result<(), GovernanceError> {
+        let bounds = config.timelock_bounds(action_type);
+        if timelock_delay < bounds.min_delay || timelock_delay > bounds.max_delay {
+            return Err(GovernanceError::TimelockOutOfRange);
+        }
+
+        Ok(())
+    }
+
+    /// Approve an action proposal
+    pub fn approve_actions(
+        env: &Env,
+        proposal_id: u64,
+        approver: Address,
+    ) -> Result<(), GovernanceError> {
+        Self::require_role(env, &approver, GovernanceRole::Approver)?;
+
+        let mut proposal = Self::get_action_proposal(env, proposal_id)?;
+
+        if proposal.status != ProposalStatus::Pending {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        if env.ledger().timestamp() > proposal.voting_deadline {
+            return Err(GovernanceError::ProposalExpired);
+        }
+
+        if !proposal.approvers.contains(&approver) {
+            return Err(GovernanceError::Unauthorized);
+        }
+
+        Self::record_approval(env, proposal_id, approver)?;
+
+        proposal.approvals_count += 1;
+        if proposal.approvals_count >= proposal.approval_threshold {
+            proposal.status = ProposalStatus::Approved;
+        }
+
+        Self::save_action_proposal(env, proposal);
+
+        Ok(())
+    }
+
+    /// Execute an approved action proposal after its timelock.
+    ///
+    /// Every action goes through `safe_invoke`; if any call fails the whole
+    /// execution fails and the proposal stays approved.
+    pub fn execute_actions(
+        env: &Env,
+        proposal_id: u64,
+        executor: Address,
+    ) -> Result<Vec<Val>, GovernanceError> {
+        Self::require_role(env, &executor, GovernanceRole::Executor)?;
+
+        let mut proposal = Self::get_action_proposal(env, proposal_id)?;
+
+        if proposal.status != ProposalStatus::Approved {
+            return Err(GovernanceError::ProposalNotApproved);
+        }
+
+        if env.ledger().timestamp() < proposal.execution_time {
+            return Err(GovernanceError::TimelockNotExpired);
+        }
+
+        if env.ledger().timestamp() > proposal.execution_time + proposal.execution_grace_period {
+            return Err(GovernanceError::ProposalExpired);
+        }
+
+        // Mark executed before calling out so actions can't re-run it
+        proposal.executed = true;
+        proposal.status = ProposalStatus::Executed;
+        Self::save_action_proposal(env, proposal.clone());
+
+        let mut results = Vec::new(env);
+        for action in proposal.actions.iter() {
+            let result = safe_invoke(env, &action.target, &action.function, action.args.clone())
+                .map_err(|_| GovernanceError::ActionFailed)?;
+            results.push_back(result);
+        }
+
+        env.events()
+            .publish((symbol_short!("prop_exec"), proposal_id), proposal.actions.len());
+
+        Ok(results)
+    }
+
+    /// Reject a pending action proposal
+    pub fn reject_actions(
+        env: &Env,
+        proposal_id: u64,
+        rejector: Address,
+    ) -> Result<(), GovernanceError> {
+        Self::require_role(env, &rejector, GovernanceRole::Approver)?;
+
+        let mut proposal = Self::get_action_proposal(env, proposal_id)?;
+
+        if proposal.status != ProposalStatus::Pending {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        proposal.status = ProposalStatus::Rejected;
+        Self::save_action_proposal(env, proposal);
+
+        Ok(())
+    }
+
+    /// Cancel an action proposal that hasn't executed (admin only)
+    pub fn cancel_actions(
+        env: &Env,
+        proposal_id: u64,
+        admin: Address,
+    ) -> Result<(), GovernanceError> {
+        Self::require_role(env, &admin, GovernanceRole::Admin)?;
+
+        let mut proposal = Self::get_action_proposal(env, proposal_id)?;
 
         if proposal.executed {
             return Err(GovernanceError::InvalidProposal);
         }
 
         proposal.status = ProposalStatus::Cancelled;
-        proposals.set(proposal_id, proposal);
-        env.storage().persistent().set(&proposals_key, &proposals);
+        Self::save_action_proposal(env, proposal);
 
         Ok(())
     }
 
+    /// Proposal lifetime settings, falling back to the defaults
+    pub fn get_config(env: &Env) -> GovernanceConfig {
+        env.storage()
+            .persistent()
+            .get(&symbol_short!("gov_cfg"))
+            .unwrap_or_else(GovernanceConfig::default_config)
+    }
+
+    /// Update proposal lifetime settings for proposals created afterwards (admin only)
+    pub fn set_config(
+        env: &Env,
+        admin: Address,
+        config: GovernanceConfig,
+    ) -> Result<(), GovernanceError> {
+        Self::require_role(env, &admin, GovernanceRole::Admin)?;
+
+        if config.voting_period == 0 || config.execution_grace_period == 0 {
+            return Err(GovernanceError::InvalidGovernanceConfig);
+        }
+
+        for bounds in [
+            &config.upgrade_timelock,
+            &config.parameter_timelock,
+            &config.emergency_timelock,
+        ] {
+            if bounds.min_delay > bounds.max_delay {
+                return Err(GovernanceError::InvalidGovernanceConfig);
+            }
+        }
+
+        if config.emergency_threshold_bps == 0 || config.emergency_threshold_bps > BPS_DENOMINATOR {
+            return Err(GovernanceError::InvalidGovernanceConfig);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&symbol_short!("gov_cfg"), &config);
+
+        Ok(())
+    }
+
+    /// Whether a proposal missed its approval deadline or execution window
+    fn is_expirable(
+        env: &Env,
+        status: ProposalStatus,
+        voting_deadline: u64,
+        execution_time: u64,
+        execution_grace_period: u64,
+    ) -> bool {
+        let now = env.ledger().timestamp();
+        match status {
+            ProposalStatus::Pending => now > voting_deadline,
+            ProposalStatus::Approved => now > execution_time + execution_grace_period,
+            _ => false,
+        }
+    }
+
+    /// IDs of upgrade and action proposals that `expire_proposal` would
+    /// expire, among the `limit` IDs after `after_id`. `limit` is capped at
+    /// `MAX_PAGE_SIZE`; sweep until `after_id` reaches `proposal_count`.
+    pub fn get_expirable_proposals(env: &Env, after_id: u64, limit: u32) -> Vec<u64> {
+        let mut ids = Vec::new(env);
+        let limit = u64::from(limit.min(MAX_PAGE_SIZE));
+        let end = Self::proposal_count(env).min(after_id.saturating_add(limit));
+
+        for id in after_id.saturating_add(1)..=end {
+            if let Ok(p) = Self::get_proposal(env, id) {
+                if Self::is_expirable(
+                    env,
+                    p.status,
+                    p.voting_deadline,
+                    p.execution_time,
+                    p.execution_grace_period,
+                ) {
+                    ids.push_back(id);
+                }
+            } else if let Ok(p) = Self::get_action_proposal(env, id) {
+                if Self::is_expirable(
+                    env,
+                    p.status,
+                    p.voting_deadline,
+                    p.execution_time,
+                    p.execution_grace_period,
+                ) {
+                    ids.push_back(id);
+                }
+            }
+        }
+
+        ids
+    }
+
+    /// Mark a stale proposal Expired. Anyone may call this.
+    pub fn expire_proposal(env: &Env, proposal_id: u64) -> Result<(), GovernanceError> {
+        if let Ok(mut proposal) = Self::get_action_proposal(env, proposal_id) {
+            if !Self::is_expirable(
+                env,
+                proposal.status,
+                proposal.voting_deadline,
+                proposal.execution_time,
+                proposal.execution_grace_period,
+            ) {
+                return Err(GovernanceError::ProposalNotExpired);
+            }
+
+            proposal.status = ProposalStatus::Expired;
+            Self::save_action_proposal(env, proposal);
+        } else {
+            let mut proposal = Self::get_proposal(env, proposal_id)?;
+
+            if !Self::is_expirable(
+                env,
+                proposal.status,
+                proposal.voting_deadline,
+                proposal.execution_time,
+                proposal.execution_grace_period,
+            ) {
+                return Err(GovernanceError::ProposalNotExpired);
+            }
+
+            proposal.status = ProposalStatus::Expired;
+            Self::save_proposal(env, &proposal);
+        }
+
+        env.events()
+            .publish((symbol_short!("prop_exp"), proposal_id), ());
+
+        Ok(())
+    }
+
+    /// IDs from a per-kind index that fall among the `limit` proposal IDs
+    /// after `after_id`, with `limit` capped at `MAX_PAGE_SIZE`
+    fn index_page(
+        env: &Env,
+        counter_key: Symbol,
+        index_key: fn(u64) -> DataKey,
+        after_id: u64,
+        limit: u32,
+    ) -> Vec<u64> {
+        let mut ids = Vec::new(env);
+        let last_id = after_id.saturating_add(u64::from(limit.min(MAX_PAGE_SIZE)));
+        let len = Self::count(env, &counter_key);
+        let id_at = |position: u64| -> u64 {
+            env.storage().persistent().get(&index_key(position)).unwrap_or(0)
+        };
+
+        // IDs are appended in increasing order, so find the first one past
+        // `after_id` by bisection
+        let (mut start, mut end) = (0, len);
+        while start < end {
+            let mid = start + (end - start) / 2;
+            if id_at(mid) <= after_id {
+                start = mid + 1;
+            } else {
+                end = mid;
+            }
+        }
+
+        for position in start..len {
+            let id = id_at(position);
+            if id > last_id {
+                break;
+            }
+            ids.push_back(id);
+        }
+
+        ids
+    }
+
+    /// Page through upgrade proposals in creation order, optionally filtered
+    /// by status. A page holds the upgrade proposals among the `limit`
+    /// proposal IDs after `after_id`, the same window `get_expirable_proposals`
+    /// uses, so it may hold fewer than `limit` entries; sweep until `after_id`
+    /// reaches `proposal_count`. `limit` is capped at `MAX_PAGE_SIZE`.
+    pub fn list_proposals(
+        env: &Env,
+        status: Option<ProposalStatus>,
+        after_id: u64,
+        limit: u32,
+    ) -> Vec<UpgradeProposal> {
+        let mut page = Vec::new(env);
+        let ids = Self::index_page(
+            env,
+            symbol_short!("upg_cnt"),
+            DataKey::ProposalIndex,
+            after_id,
+            limit,
+        );
+
+        for id in ids.iter() {
+            let Ok(proposal) = Self::get_proposal(env, id) else {
+                continue;
+            };
+            if status.is_none_or(|s| s == proposal.status) {
+                page.push_back(proposal);
+            }
+        }
+
+        page
+    }
+
+    /// Page through action proposals; same rules as `list_proposals`
+    pub fn list_action_proposals(
+        env: &Env,
+        status: Option<ProposalStatus>,
+        after_id: u64,
+        limit: u32,
+    ) -> Vec<Proposal> {
+        let mut page = Vec::new(env);
+        let ids = Self::index_page(
+            env,
+            symbol_short!("act_cnt"),
+            DataKey::ActionProposalIndex,
+            after_id,
+            limit,
+        );
+
+        for id in ids.iter() {
+            let Ok(proposal) = Self::get_action_proposal(env, id) else {
+                continue;
+            };
+            if status.is_none_or(|s| s == proposal.status) {
+                page.push_back(proposal);
+            }
+        }
+
+        page
+    }
+
+    /// Rewrite the `"props"` and `"act_props"` maps from their earlier shape,
+    /// giving each proposal a voting deadline and grace period from the
+    /// current config
+    pub fn migrate_legacy_proposals(env: &Env) {
+        let storage = env.storage().persistent();
+        let config = Self::get_config(env);
+
+        let legacy: Option<soroban_sdk::Map<u64, Val>> = storage.get(&symbol_short!("props"));
+        if let Some(legacy) = legacy {
+            let mut upgrades = soroban_sdk::Map::new(env);
+            for (id, raw) in legacy.iter() {
+                let old = Self::decode_legacy_upgrade(env, raw);
+                let proposal = UpgradeProposal {
+                    id: old.id,
+                    proposer: old.proposer,
+                    new_contract_hash: old.new_contract_hash,
+                    target_contract: old.target_contract,
+                    description: old.description,
+                    approval_threshold: old.approval_threshold,
+                    approvers: old.approvers,
+                    approvals_count: old.approvals_count,
+                    status: old.status,
+                    created_at: old.created_at,
+                    execution_time: old.execution_time,
+                    voting_deadline: old.created_at + config.voting_period,
+                    execution_grace_period: config.execution_grace_period,
+                    executed: old.executed,
+                };
+                upgrades.set(id, proposal);
+            }
+            storage.set(&symbol_short!("props"), &upgrades);
+        }
+
+        let legacy: Option<soroban_sdk::Map<u64, ProposalV3>> =
+            storage.get(&symbol_short!("act_props"));
+        if let Some(legacy) = legacy {
+            let mut actions = soroban_sdk::Map::new(env);
+            for (id, old) in legacy.iter() {
+                let proposal = Proposal {
+                    id: old.id,
+                    proposer: old.proposer,
+                    actions: old.actions,
+                    description: old.description,
+                    approval_threshold: old.approval_threshold,
+                    approvers: old.approvers,
+                    approvals_count: old.approvals_count,
+                    status: old.status,
+                    created_at: old.created_at,
+                    execution_time: old.execution_time,
+                    voting_deadline: old.created_at + config.voting_period,
+                    execution_grace_period: config.execution_grace_period,
+                    executed: old.executed,
+                };
+                actions.set(id, proposal);
+            }
+            storage.set(&symbol_short!("act_props"), &actions);
+        }
+    }
+
+    /// Decode an upgrade proposal from either legacy layout. Original-layout
+    /// proposals named their code by `Symbol`, which can never be installed,
+    /// so open ones come back cancelled with a zero hash.
+    fn decode_legacy_upgrade(env: &Env, raw: Val) -> UpgradeProposalV3 {
+        if let Ok(proposal) = UpgradeProposalV3::try_from_val(env, &raw) {
+            return proposal;
+        }
+
+        let old = UpgradeProposalV1::try_from_val(env, &raw).expect("unknown upgrade proposal layout");
+        let status = match old.status {
+            ProposalStatus::Pending | ProposalStatus::Approved => ProposalStatus::Cancelled,
+            status => status,
+        };
+
+        UpgradeProposalV3 {
+            id: old.id,
+            proposer: old.proposer,
+            new_contract_hash: BytesN::from_array(env, &[0; 32]),
+            target_contract: old.target_contract,
+            description: old.description,
+            approval_threshold: old.approval_threshold,
+            approvers: old.approvers,
+            approvals_count: old.approvals_count,
+            status,
+            created_at: old.created_at,
+            execution_time: old.execution_time,
+            executed: old.executed,
+        }
+    }
+
+    /// Move proposals and approvals out of the `"props"`, `"act_props"` and
+    /// `"apprv"` maps into one entry each
+    pub fn migrate_proposal_maps(env: &Env) {
+        let storage = env.storage().persistent();
+
+        let upgrades: Option<soroban_sdk::Map<u64, UpgradeProposal>> =
+            storage.get(&symbol_short!("props"));
+        for (_, proposal) in upgrades.into_iter().flatten() {
+            Self::save_proposal(env, &proposal);
+        }
+
+        let actions: Option<soroban_sdk::Map<u64, Proposal>> =
+            storage.get(&symbol_short!("act_props"));
+        for (_, proposal) in actions.into_iter().flatten() {
+            Self::save_action_proposal(env, proposal);
+        }
+
+        let approvals: Option<soroban_sdk::Map<(u64, Address), bool>> =
+            storage.get(&symbol_short!("apprv"));
+        for ((proposal_id, approver), _) in approvals.into_iter().flatten() {
+            let key = DataKey::Approval(proposal_id, approver);
+            storage.set(&key, &true);
+            Self::extend_ttl(env, &key);
+        }
+
+        storage.remove(&symbol_short!("props"));
+        storage.remove(&symbol_short!("act_props"));
+        storage.remove(&symbol_short!("apprv"));
+    }
+
+    /// Build the per-kind proposal indexes from the shared ID range and start
+    /// the role-change counter past every ID already handed out, since role
+    /// changes used to draw from `"prop_cnt"`
+    pub fn migrate_proposal_indexes(env: &Env) {
+        let storage = env.storage().persistent();
+        let count = Self::proposal_count(env);
+        storage.set(&symbol_short!("upg_cnt"), &0u64);
+        storage.set(&symbol_short!("act_cnt"), &0u64);
+
+        for id in 1..=count {
+            if Self::get_proposal(env, id).is_ok() {
+                Self::append_index(env, symbol_short!("upg_cnt"), DataKey::ProposalIndex, id);
+            } else if Self::get_action_proposal(env, id).is_ok() {
+                Self::append_index(env, symbol_short!("act_cnt"), DataKey::ActionProposalIndex, id);
+            }
+        }
+
+        storage.set(&symbol_short!("role_cnt"), &count);
+    }
+
+    /// Get an action proposal by ID
+    pub fn get_action_proposal(env: &Env, proposal_id: u64) -> Result<Proposal, GovernanceError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ActionProposal(proposal_id))
+            .ok_or(GovernanceError::ProposalNotFound)
+    }
+
     /// Get a proposal by ID
     pub fn get_proposal(
         env: &Env,
         proposal_id: u64,
     ) -> Result<UpgradeProposal, GovernanceError> {
-        let proposals_key = symbol_short!("props");
-        let proposals: soroban_sdk::Map<u64, UpgradeProposal> = env
-            .storage()
+        env.storage()
             .persistent()
-            .get(&proposals_key)
-            .ok_or(GovernanceError::ProposalNotFound)?;
-
-        proposals
-            .get(proposal_id)
+            .get(&DataKey::Proposal(proposal_id))
             .ok_or(GovernanceError::ProposalNotFound)
     }
 }
@@ -0,0 +1,499 @@
+//! Token-weighted voting, usable alongside the M-of-N approver flow.
+//!
+//! Voting power is the voter's token balance snapshotted when a proposal is
+//! created. Only registered voters are snapshotted, which keeps creation
+//! bounded and stops the same tokens voting twice after a transfer. When the
+//! registry is full, a larger holder takes the slot of the smallest one, so
+//! dust balances can't lock real holders out. The voting rules are
+//! snapshotted too, so a config change only affects proposals created after it.
+
+use soroban_sdk::{contracttype, symbol_short, token, Address, Env, IntoVal, Symbol, Val, Vec};
+
+use super::{
+    GovernanceError, GovernanceManager, ProposalAction, PROPOSAL_TTL_EXTEND,
+    PROPOSAL_TTL_THRESHOLD,
+};
+use crate::fees::BPS_DENOMINATOR;
+use crate::safe_call::safe_invoke;
+
+/// Maximum number of registered voters snapshotted per proposal. `propose`
+/// makes one balance call and one write per voter, so this keeps proposal
+/// creation inside a transaction's ledger-entry limits. A full registry
+/// evicts its smallest holder rather than refusing larger ones.
+pub const MAX_VOTERS: u32 = 20;
+
+/// Vote options
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum VoteType {
+    Against = 0,
+    For = 1,
+    Abstain = 2,
+}
+
+/// Lifecycle of a voting proposal
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum VotingState {
+    Pending = 0,   // Created, voting not started
+    Active = 1,    // Accepting votes
+    Succeeded = 2, // Quorum and threshold met
+    Defeated = 3,  // Quorum or threshold missed
+    Queued = 4,    // In timelock
+    Executed = 5,
+    Cancelled = 6,
+    Expired = 7,   // Queued but not executed within its grace period
+}
+
+/// Voting rules for the host contract
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VotingConfig {
+    pub token: Address,            // Token whose balances give voting power
+    pub voting_delay: u64,         // Seconds from creation until voting opens
+    pub voting_period: u64,        // Seconds voting stays open
+    pub quorum_bps: u32,           // Share of snapshotted supply that must vote (any option)
+    pub pass_threshold_bps: u32,   // Share of for + against votes that must be for
+    pub proposal_threshold: i128,  // Balance needed to create a proposal
+    pub timelock_delay: u64,       // Seconds between queueing and execution
+    pub allow_vote_change: bool,   // Whether voters may change their vote while active
+}
+
+/// Token-weighted proposal
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct VotingProposal {
+    pub id: u64,
+    pub proposer: Address,
+    pub actions: Vec<ProposalAction>,
+    pub description: Symbol,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub total_weight: i128,        // Sum of snapshotted balances
+    pub for_votes: i128,
+    pub against_votes: i128,
+    pub abstain_votes: i128,
+    pub eta: u64,                  // Earliest execution time once queued
+    pub execution_grace_period: u64, // Seconds after eta that execution stays open
+    pub queued: bool,
+    pub executed: bool,
+    pub cancelled: bool,
+    pub config: VotingConfig,      // Rules in force when the proposal was created
+}
+
+/// `VotingProposal` as stored through contract version 6, before the
+/// voting rules and grace period were snapshotted
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct VotingProposalV6 {
+    pub id: u64,
+    pub proposer: Address,
+    pub actions: Vec<ProposalAction>,
+    pub description: Symbol,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub total_weight: i128,
+    pub for_votes: i128,
+    pub against_votes: i128,
+    pub abstain_votes: i128,
+    pub eta: u64,
+    pub queued: bool,
+    pub executed: bool,
+    pub cancelled: bool,
+}
+
+/// A voter's current vote on a proposal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VoteReceipt {
+    pub support: VoteType,
+    pub weight: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+enum VotingKey {
+    Config,
+    Voters,
+    Count,
+    Proposal(u64),
+    Weight(u64, Address),  // Snapshotted balance per (proposal, voter)
+    Receipt(u64, Address), // Vote per (proposal, voter)
+}
+
+pub struct TokenVoting;
+
+impl TokenVoting {
+    /// Set the voting rules. The caller is responsible for authorizing the change.
+    pub fn set_config(env: &Env, config: VotingConfig) -> Result<(), GovernanceError> {
+        if config.voting_period == 0
+            || config.quorum_bps > BPS_DENOMINATOR
+            || config.pass_threshold_bps > BPS_DENOMINATOR
+            || config.proposal_threshold < 0
+        {
+            return Err(GovernanceError::InvalidVotingConfig);
+        }
+
+        Self::store(env, &VotingKey::Config, &config);
+
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env) -> Result<VotingConfig, GovernanceError> {
+        env.storage()
+            .persistent()
+            .get(&VotingKey::Config)
+            .ok_or(GovernanceError::InvalidVotingConfig)
+    }
+
+    /// Add a token holder to the set snapshotted by new proposals. Once the
+    /// set is full, `voter` replaces the registered voter with the smallest
+    /// current balance if its own balance is larger.
+    /// The caller is responsible for authorizing `voter`.
+    pub fn register_voter(env: &Env, voter: &Address) -> Result<(), GovernanceError> {
+        let config = Self::get_config(env)?;
+        let mut voters = Self::get_voters(env);
+
+        if voters.contains(voter) {
+            return Ok(());
+        }
+
+        let token_client = token::Client::new(env, &config.token);
+        let balance = token_client.balance(voter);
+        if balance <= 0 {
+            return Err(GovernanceError::NoVotingPower);
+        }
+
+        if voters.len() >= MAX_VOTERS {
+            let mut smallest = (0u32, i128::MAX);
+            for (index, registered) in voters.iter().enumerate() {
+                let registered_balance = token_client.balance(&registered);
+                if registered_balance < smallest.1 {
+                    smallest = (index as u32, registered_balance);
+                }
+            }
+            if balance <= smallest.1 {
+                return Err(GovernanceError::TooManyVoters);
+            }
+
+            let evicted = voters.get_unchecked(smallest.0);
+            voters.remove(smallest.0);
+            env.events()
+                .publish((symbol_short!("vtr_evict"), evicted), smallest.1);
+        }
+
+        voters.push_back(voter.clone());
+        Self::store(env, &VotingKey::Voters, &voters);
+
+        Ok(())
+    }
+
+    /// Stop new proposals from snapshotting `voter`. Proposals already
+    /// created keep its weight. The caller is responsible for authorizing `voter`.
+    pub fn unregister_voter(env: &Env, voter: &Address) -> Result<(), GovernanceError> {
+        let mut voters = Self::get_voters(env);
+        let Some(index) = voters.first_index_of(voter) else {
+            return Err(GovernanceError::NoVotingPower);
+        };
+
+        voters.remove(index);
+        Self::store(env, &VotingKey::Voters, &voters);
+
+        Ok(())
+    }
+
+    pub fn get_voters(env: &Env) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&VotingKey::Voters)
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Create a proposal and snapshot every registered voter's balance.
+    /// The caller is responsible for authorizing `proposer`.
+    pub fn propose(
+        env: &Env,
+        proposer: Address,
+        actions: Vec<ProposalAction>,
+        description: Symbol,
+    ) -> Result<u64, GovernanceError> {
+        let config = Self::get_config(env)?;
+        if actions.is_empty() {
+            return Err(GovernanceError::InvalidProposal);
+        }
+
+        let token_client = token::Client::new(env, &config.token);
+        if token_client.balance(&proposer) < config.proposal_threshold {
+            return Err(GovernanceError::NoVotingPower);
+        }
+
+        let id: u64 = env
+            .storage()
+            .persistent()
+            .get(&VotingKey::Count)
+            .unwrap_or(0u64)
+            + 1;
+
+        let mut total_weight = 0i128;
+        for voter in Self::get_voters(env).iter() {
+            let balance = token_client.balance(&voter);
+            if balance > 0 {
+                total_weight += balance;
+                Self::store(env, &VotingKey::Weight(id, voter), &balance);
+            }
+        }
+
+        let start_time = env.ledger().timestamp() + config.voting_delay;
+        let proposal = VotingProposal {
+            id,
+            proposer,
+            actions,
+            description,
+            start_time,
+            end_time: start_time + config.voting_period,
+            total_weight,
+            for_votes: 0,
+            against_votes: 0,
+            abstain_votes: 0,
+            eta: 0,
+            execution_grace_period: GovernanceManager::get_config(env).execution_grace_period,
+            queued: false,
+            executed: false,
+            cancelled: false,
+            config,
+        };
+
+        Self::save_proposal(env, &proposal);
+        Self::store(env, &VotingKey::Count, &id);
+
+        env.events()
+            .publish((symbol_short!("vote_new"), id), proposal.end_time);
+
+        Ok(id)
+    }
+
+    /// Cast or, if the config allows it, change a vote while the proposal is active.
+    /// The caller is responsible for authorizing `voter`.
+    pub fn cast_vote(
+        env: &Env,
+        proposal_id: u64,
+        voter: &Address,
+        support: VoteType,
+    ) -> Result<i128, GovernanceError> {
+        let mut proposal = Self::get_proposal(env, proposal_id)?;
+
+        if Self::state(env, proposal_id)? != VotingState::Active {
+            return Err(GovernanceError::VotingClosed);
+        }
+
+        let weight = Self::voting_power(env, proposal_id, voter);
+        if weight == 0 {
+            return Err(GovernanceError::NoVotingPower);
+        }
+
+        let receipt_key = VotingKey::Receipt(proposal_id, voter.clone());
+        if let Some(previous) = env.storage().persistent().get::<_, VoteReceipt>(&receipt_key) {
+            if !proposal.config.allow_vote_change {
+                return Err(GovernanceError::AlreadyVoted);
+            }
+            Self::tally(&mut proposal, previous.support, -previous.weight);
+        }
+
+        Self::tally(&mut proposal, support, weight);
+        Self::save_proposal(env, &proposal);
+        Self::store(env, &receipt_key, &VoteReceipt { support, weight });
+
+        env.events()
+            .publish((symbol_short!("vote"), proposal_id, voter.clone()), (support, weight));
+
+        Ok(weight)
+    }
+
+    fn tally(proposal: &mut VotingProposal, support: VoteType, weight: i128) {
+        match support {
+            VoteType::For => proposal.for_votes += weight,
+            VoteType::Against => proposal.against_votes += weight,
+            VoteType::Abstain => proposal.abstain_votes += weight,
+        }
+    }
+
+    /// Current state of a proposal, derived from time and tallies
+    pub fn state(env: &Env, proposal_id: u64) -> Result<VotingState, GovernanceError> {
+        let proposal = Self::get_proposal(env, proposal_id)?;
+        let now = env.ledger().timestamp();
+
+        if proposal.cancelled {
+            return Ok(VotingState::Cancelled);
+        }
+        if proposal.executed {
+            return Ok(VotingState::Executed);
+        }
+        if proposal.queued {
+            if now > proposal.eta + proposal.execution_grace_period {
+                return Ok(VotingState::Expired);
+            }
+            return Ok(VotingState::Queued);
+        }
+        if now < proposal.start_time {
+            return Ok(VotingState::Pending);
+        }
+        if now <= proposal.end_time {
+            return Ok(VotingState::Active);
+        }
+
+        let config = &proposal.config;
+        let turnout = proposal.for_votes + proposal.against_votes + proposal.abstain_votes;
+        let quorum_met = turnout * BPS_DENOMINATOR as i128
+            >= proposal.total_weight * config.quorum_bps as i128;
+        let decided = proposal.for_votes + proposal.against_votes;
+        let passed = proposal.for_votes > 0
+            && proposal.for_votes * BPS_DENOMINATOR as i128
+                >= decided * config.pass_threshold_bps as i128;
+
+        if quorum_met && passed {
+            Ok(VotingState::Succeeded)
+        } else {
+            Ok(VotingState::Defeated)
+        }
+    }
+
+    /// Move a succeeded proposal into its timelock
+    pub fn queue(env: &Env, proposal_id: u64) -> Result<u64, GovernanceError> {
+        if Self::state(env, proposal_id)? != VotingState::Succeeded {
+            return Err(GovernanceError::InvalidVotingState);
+        }
+
+        let mut proposal = Self::get_proposal(env, proposal_id)?;
+        proposal.queued = true;
+        proposal.eta = env.ledger().timestamp() + proposal.config.timelock_delay;
+        Self::save_proposal(env, &proposal);
+
+        env.events()
+            .publish((symbol_short!("vote_que"), proposal_id), proposal.eta);
+
+        Ok(proposal.eta)
+    }
+
+    /// Run a queued proposal's actions once its timelock has passed
+    pub fn execute(env: &Env, proposal_id: u64) -> Result<Vec<Val>, GovernanceError> {
+        if Self::state(env, proposal_id)? != VotingState::Queued {
+            return Err(GovernanceError::InvalidVotingState);
+        }
+
+        let mut proposal = Self::get_proposal(env, proposal_id)?;
+        if env.ledger().timestamp() < proposal.eta {
+            return Err(GovernanceError::TimelockNotExpired);
+        }
+
+        proposal.executed = true;
+        Self::save_proposal(env, &proposal);
+
+        let mut results = Vec::new(env);
+        for action in proposal.actions.iter() {
+            let result = safe_invoke(env, &action.target, &action.function, action.args.clone())
+                .map_err(|_| GovernanceError::ActionFailed)?;
+            results.push_back(result);
+        }
+
+        env.events()
+            .publish((symbol_short!("vote_exec"), proposal_id), proposal.actions.len());
+
+        Ok(results)
+    }
+
+    /// Cancel a proposal that has not executed.
+    /// The caller is responsible for checking who may cancel.
+    pub fn cancel(env: &Env, proposal_id: u64) -> Result<(), GovernanceError> {
+        let state = Self::state(env, proposal_id)?;
+        if state == VotingState::Executed || state == VotingState::Cancelled {
+            return Err(GovernanceError::InvalidVotingState);
+        }
+
+        let mut proposal = Self::get_proposal(env, proposal_id)?;
+        proposal.cancelled = true;
+        Self::save_proposal(env, &proposal);
+
+        Ok(())
+    }
+
+    pub fn get_proposal(env: &Env, proposal_id: u64) -> Result<VotingProposal, GovernanceError> {
+        env.storage()
+            .persistent()
+            .get(&VotingKey::Proposal(proposal_id))
+            .ok_or(GovernanceError::ProposalNotFound)
+    }
+
+    fn save_proposal(env: &Env, proposal: &VotingProposal) {
+        Self::store(env, &VotingKey::Proposal(proposal.id), proposal);
+    }
+
+    /// Write a voting entry and keep it alive as long as governance proposals
+    fn store<V: IntoVal<Env, Val>>(env: &Env, key: &VotingKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        env.storage()
+            .persistent()
+            .extend_ttl(key, PROPOSAL_TTL_THRESHOLD, PROPOSAL_TTL_EXTEND);
+    }
+
+    /// Voting power snapshotted for a voter when the proposal was created
+    pub fn voting_power(env: &Env, proposal_id: u64, voter: &Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&VotingKey::Weight(proposal_id, voter.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Give every stored proposal the current voting config, which is what
+    /// it was decided under before the rules were snapshotted, and the
+    /// current execution grace period
+    pub fn migrate_proposal_config(env: &Env) {
+        let Ok(config) = Self::get_config(env) else {
+            return;
+        };
+        let grace_period = GovernanceManager::get_config(env).execution_grace_period;
+        let count: u64 = env
+            .storage()
+            .persistent()
+            .get(&VotingKey::Count)
+            .unwrap_or(0);
+
+        for id in 1..=count {
+            let Some(old) = env
+                .storage()
+                .persistent()
+                .get::<_, VotingProposalV6>(&VotingKey::Proposal(id))
+            else {
+                continue;
+            };
+            Self::save_proposal(
+                env,
+                &VotingProposal {
+                    id: old.id,
+                    proposer: old.proposer,
+                    actions: old.actions,
+                    description: old.description,
+                    start_time: old.start_time,
+                    end_time: old.end_time,
+                    total_weight: old.total_weight,
+                    for_votes: old.for_votes,
+                    against_votes: old.against_votes,
+                    abstain_votes: old.abstain_votes,
+                    eta: old.eta,
+                    execution_grace_period: grace_period,
+                    queued: old.queued,
+                    executed: old.executed,
+                    cancelled: old.cancelled,
+                    config: config.clone(),
+                },
+            );
+        }
+    }
+
+    pub fn get_receipt(env: &Env, proposal_id: u64, voter: &Address) -> Option<VoteReceipt> {
+        env.storage()
+            .persistent()
+            .get(&VotingKey::Receipt(proposal_id, voter.clone()))
+    }
+}
@@ -1,6 +1,6 @@
 //! Shared utilities and types for Stellara contracts
 
-use soroban_sdk::contracttype;
+use soroban_sdk::{contracttype, String};
 
 #[contracttype]
 #[derive(Clone, Debug)]
@@ -12,6 +12,8 @@ pub struct ContractConfig {
 
 pub mod fees;
 pub mod governance;
+pub mod oracle;
+pub mod safe_call;
 
 /// Standard contract error codes
 pub mod errors {
@@ -0,0 +1,100 @@
+use soroban_sdk::{
+    contracterror, contracttype, Address, Env, IntoVal, Symbol, TryFromVal, Val, Vec,
+};
+
+use crate::fees::BPS_DENOMINATOR;
+use crate::safe_call::safe_invoke;
+
+/// Asset identifier as defined by SEP-40
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+/// Price reported by a SEP-40 oracle
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum OracleError {
+    OracleUnavailable = 5001,
+    PriceNotFound = 5002,
+    StalePrice = 5003,
+    PriceDeviation = 5004,
+}
+
+pub struct OracleManager;
+
+impl OracleManager {
+    /// Fetches the latest price for an asset from a SEP-40 oracle.
+    ///
+    /// # Arguments
+    /// * `env` - The environment
+    /// * `oracle` - The oracle contract address
+    /// * `asset` - The asset to price
+    ///
+    /// # Returns
+    /// * `Result<PriceData, OracleError>` - The latest price or an error
+    pub fn lastprice(env: &Env, oracle: &Address, asset: &Asset) -> Result<PriceData, OracleError> {
+        let mut args: Vec<Val> = Vec::new(env);
+        args.push_back(asset.into_val(env));
+
+        let res = safe_invoke(env, oracle, &Symbol::new(env, "lastprice"), args)
+            .map_err(|_| OracleError::OracleUnavailable)?;
+
+        let price: Option<PriceData> =
+            Option::try_from_val(env, &res).map_err(|_| OracleError::OracleUnavailable)?;
+
+        price.ok_or(OracleError::PriceNotFound)
+    }
+
+    /// Validates a price against an oracle reading.
+    ///
+    /// # Arguments
+    /// * `env` - The environment
+    /// * `price` - The price to validate
+    /// * `reference` - The oracle reading to validate against
+    /// * `max_deviation_bps` - Allowed distance from the oracle price in basis points
+    /// * `max_age` - Maximum age of the oracle reading in seconds
+    pub fn check_price(
+        env: &Env,
+        price: i128,
+        reference: &PriceData,
+        max_deviation_bps: u32,
+        max_age: u64,
+    ) -> Result<(), OracleError> {
+        let now = env.ledger().timestamp();
+        if now.saturating_sub(reference.timestamp) > max_age {
+            return Err(OracleError::StalePrice);
+        }
+
+        if reference.price <= 0 {
+            return Err(OracleError::PriceNotFound);
+        }
+
+        // |price - reference| / reference <= max_deviation_bps / 10000.
+        // A gap too large to compute is outside any allowed deviation.
+        let deviation = price
+            .checked_sub(reference.price)
+            .and_then(i128::checked_abs)
+            .and_then(|d| d.checked_mul(BPS_DENOMINATOR as i128))
+            .ok_or(OracleError::PriceDeviation)?;
+        let allowed = reference
+            .price
+            .checked_mul(max_deviation_bps as i128)
+            .ok_or(OracleError::PriceDeviation)?;
+        if deviation > allowed {
+            return Err(OracleError::PriceDeviation);
+        }
+
+        Ok(())
+    }
+}
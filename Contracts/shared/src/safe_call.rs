@@ -26,15 +26,15 @@ pub fn safe_invoke(
     // or checking ledger entries, but try_call handles non-existence as an error.
 
     // 2. Try Call
-    // invoke_contract_try returns Result<Val, Error>
+    // try_invoke_contract returns Ok(Ok(val)) on success, Ok(Err(_)) if the return
+    // value doesn't convert, and Err(_) if the callee failed.
     // We map generic errors to our specific codes if needed, or propagate.
-    
-    // Explicitly type the result to see what compiler thinks
-    let res: Result<Val, Error> = env.try_invoke_contract(contract, func, args);
+    let res: Result<Result<Val, _>, Result<Error, _>> =
+        env.try_invoke_contract::<Val, Error>(contract, func, args);
 
     match res {
-        Ok(val) => Ok(val),
-        Err(e) => {
+        Ok(Ok(val)) => Ok(val),
+        _ => {
             // Log the error for debugging
             // env.events().publish((Symbol::new(env, "call_failed"),), e);
             